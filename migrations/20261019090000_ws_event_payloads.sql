@@ -0,0 +1,8 @@
+-- Payload event yang terlalu besar untuk NOTIFY (batas 8000 byte).
+CREATE TABLE IF NOT EXISTS ws_event_payloads (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ws_event_payloads_created_at ON ws_event_payloads (created_at);
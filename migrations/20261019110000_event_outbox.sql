@@ -0,0 +1,12 @@
+-- Event yang ditulis dalam transaksi yang sama dengan perubahan datanya, lalu
+-- diterbitkan ke event bus secara berurutan oleh relay.
+CREATE TABLE IF NOT EXISTS event_outbox (
+    seq BIGSERIAL PRIMARY KEY,
+    event_id UUID NOT NULL UNIQUE,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_outbox_pending ON event_outbox (seq) WHERE delivered_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_event_outbox_delivered_at ON event_outbox (delivered_at) WHERE delivered_at IS NOT NULL;
@@ -0,0 +1,3 @@
+-- Id transaksi yang menulis event, dipakai untuk menentukan apakah event sudah
+-- tercermin dalam snapshot data yang dikirim ke client WebSocket.
+ALTER TABLE event_outbox ADD COLUMN IF NOT EXISTS xact_id XID8 NOT NULL DEFAULT pg_current_xact_id();
@@ -0,0 +1,3 @@
+-- Indeks untuk keyset pagination listing buku dan email.
+CREATE INDEX IF NOT EXISTS idx_books_created_at_id ON books (created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS idx_emails_sent_at_id ON emails (sent_at DESC, id DESC);
@@ -0,0 +1,14 @@
+-- Kolom tsvector untuk pencarian teks penuh. Konfigurasi 'simple' dipakai karena isi
+-- bercampur bahasa Indonesia dan Inggris; bobot A untuk judul/subjek.
+ALTER TABLE books ADD COLUMN IF NOT EXISTS search_vector TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', coalesce(title, '')), 'A') ||
+    setweight(to_tsvector('simple', coalesce(author, '')), 'B')
+) STORED;
+
+ALTER TABLE emails ADD COLUMN IF NOT EXISTS search_vector TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', coalesce(subject, '')), 'A') ||
+    setweight(to_tsvector('simple', coalesce(body, '')), 'B')
+) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_books_search_vector ON books USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_emails_search_vector ON emails USING GIN (search_vector);
@@ -0,0 +1,3 @@
+-- Nomor versi baris untuk ETag dan optimistic concurrency (If-Match).
+ALTER TABLE books ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE emails ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 1;
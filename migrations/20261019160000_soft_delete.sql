@@ -0,0 +1,7 @@
+-- Soft delete: baris yang dihapus diberi `deleted_at` dan dibersihkan setelah masa retensi.
+ALTER TABLE books ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE emails ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+-- Indeks untuk listing tempat sampah dan purge.
+CREATE INDEX IF NOT EXISTS idx_books_deleted_at_id ON books (deleted_at DESC, id DESC) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_emails_deleted_at_id ON emails (deleted_at DESC, id DESC) WHERE deleted_at IS NOT NULL;
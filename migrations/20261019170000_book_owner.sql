@@ -0,0 +1,3 @@
+-- Pemilik buku. Buku lama tidak punya pemilik dan hanya bisa diubah admin.
+ALTER TABLE books ADD COLUMN IF NOT EXISTS created_by UUID;
+CREATE INDEX IF NOT EXISTS idx_books_created_by ON books (created_by);
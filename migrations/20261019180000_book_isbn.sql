@@ -0,0 +1,9 @@
+-- ISBN buku, selalu disimpan sebagai ISBN-13 tanpa tanda hubung.
+ALTER TABLE books ADD COLUMN IF NOT EXISTS isbn TEXT;
+-- ADD CONSTRAINT tidak punya IF NOT EXISTS, jadi dicek lewat katalog.
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'books_isbn_key') THEN
+        ALTER TABLE books ADD CONSTRAINT books_isbn_key UNIQUE (isbn);
+    END IF;
+END $$;
@@ -0,0 +1,42 @@
+CREATE TABLE IF NOT EXISTS authors (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    bio TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    search_vector TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple', coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('simple', coalesce(bio, '')), 'B')
+    ) STORED
+);
+
+CREATE INDEX IF NOT EXISTS idx_authors_created_at_id ON authors (created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS idx_authors_search_vector ON authors USING GIN (search_vector);
+
+-- Relasi buku-penulis. `position` menentukan urutan tampil dalam satu buku; penulis yang
+-- sama boleh muncul dengan peran berbeda (misalnya penulis sekaligus penerjemah).
+CREATE TABLE IF NOT EXISTS book_authors (
+    book_id UUID NOT NULL REFERENCES books (id) ON DELETE CASCADE,
+    author_id UUID NOT NULL REFERENCES authors (id) ON DELETE RESTRICT,
+    role TEXT NOT NULL DEFAULT 'author' CHECK (role IN ('author', 'editor', 'translator')),
+    position INT NOT NULL,
+    PRIMARY KEY (book_id, author_id, role),
+    UNIQUE (book_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS idx_book_authors_author_id ON book_authors (author_id);
+
+-- Satu penulis untuk setiap teks `books.author` yang berbeda setelah spasi dirapikan dan
+-- huruf besar/kecil diabaikan. Variasi penulisan lain ("Tolkien, J.R.R.") tetap menjadi
+-- penulis terpisah dan bisa disatukan admin lewat `POST /authors/{id}/merge`.
+INSERT INTO authors (name)
+SELECT DISTINCT ON (lower(name)) name
+FROM (SELECT btrim(regexp_replace(author, '\s+', ' ', 'g')) AS name FROM books) normalized
+WHERE name <> ''
+ORDER BY lower(name), name;
+
+INSERT INTO book_authors (book_id, author_id, role, position)
+SELECT b.id, a.id, 'author', 0
+FROM books b
+JOIN authors a ON lower(a.name) = lower(btrim(regexp_replace(b.author, '\s+', ' ', 'g')))
+ON CONFLICT DO NOTHING;
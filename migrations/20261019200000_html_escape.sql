@@ -0,0 +1,7 @@
+-- Escape HTML untuk teks yang diberi `<mark>` oleh `ts_headline`, sehingga snippet
+-- pencarian aman ditampilkan sebagai HTML. `&` harus diganti paling awal.
+CREATE OR REPLACE FUNCTION html_escape(input TEXT) RETURNS TEXT
+LANGUAGE sql IMMUTABLE STRICT PARALLEL SAFE AS $$
+    SELECT replace(replace(replace(replace(replace(input,
+        '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '"', '&quot;'), '''', '&#39;')
+$$;
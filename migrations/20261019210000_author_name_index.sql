@@ -0,0 +1,3 @@
+-- Pencarian penulis berdasarkan nama tanpa membedakan huruf besar/kecil saat buku
+-- dibuat atau diimpor.
+CREATE INDEX IF NOT EXISTS idx_authors_lower_name ON authors (lower(name));
@@ -0,0 +1,3 @@
+-- Relay menerbitkan event per transaksi penulisnya, lalu per `seq`.
+DROP INDEX IF EXISTS idx_event_outbox_pending;
+CREATE INDEX IF NOT EXISTS idx_event_outbox_pending_xact ON event_outbox (xact_id, seq) WHERE delivered_at IS NULL;
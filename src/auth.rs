@@ -14,6 +14,7 @@ use axum::{
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 // The Claims struct and its impl block remain unchanged...
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -36,6 +37,23 @@ impl Claims {
         Self { sub, role, iat: iat.timestamp(), exp: exp.timestamp() }
     }
 
+    pub fn is_admin(&self) -> bool {
+        self.role == "admin"
+    }
+
+    pub fn require_admin(&self) -> Result<(), AppError> {
+        if self.is_admin() { Ok(()) } else { Err(AppError::Forbidden) }
+    }
+
+    pub fn user_id(&self) -> Option<Uuid> {
+        Uuid::parse_str(&self.sub).ok()
+    }
+
+    // Record hanya boleh diubah pemiliknya atau admin.
+    pub fn can_modify(&self, owner: Option<Uuid>) -> bool {
+        self.is_admin() || owner.is_some_and(|owner| Some(owner) == self.user_id())
+    }
+
     pub fn encode(&self) -> Result<String, AppError> {
         let secret = std::env::var("JWT_SECRET").expect("JWT_SECRET harus diatur");
         encode(&Header::default(), self, &EncodingKey::from_secret(secret.as_ref()))
@@ -0,0 +1,267 @@
+use crate::models::book::Book;
+use serde::Deserialize;
+
+// Record tunggal yang lebih besar dari ini dianggap body rusak (misalnya kutip CSV
+// yang tidak pernah ditutup) agar buffer tidak tumbuh tanpa batas.
+const MAX_RECORD_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkFormat {
+    Csv,
+    #[default]
+    Json,
+    Ndjson,
+}
+
+impl BulkFormat {
+    pub fn from_content_type(value: &str) -> Option<Self> {
+        let mime = value.split(';').next()?.trim().to_ascii_lowercase();
+        match mime.as_str() {
+            "text/csv" => Some(Self::Csv),
+            "application/json" => Some(Self::Json),
+            "application/x-ndjson" | "application/ndjson" => Some(Self::Ndjson),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Json => "application/json",
+            Self::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+            Self::Ndjson => "ndjson",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JsonState {
+    BeforeArray,
+    BetweenItems,
+    InItem,
+    AfterArray,
+}
+
+// Memecah body yang datang per potongan menjadi satu record per baris (CSV, NDJSON) atau
+// per elemen array (JSON) tanpa menampung seluruh body. Baris CSV boleh memuat newline
+// di dalam kutip.
+pub struct RecordSplitter {
+    format: BulkFormat,
+    buf: Vec<u8>,
+    // Posisi byte berikutnya yang belum dipindai dan awal record yang sedang dibaca.
+    scanned: usize,
+    start: usize,
+    in_string: bool,
+    escaped: bool,
+    depth: usize,
+    json: JsonState,
+}
+
+impl RecordSplitter {
+    pub fn new(format: BulkFormat) -> Self {
+        Self {
+            format,
+            buf: Vec::new(),
+            scanned: 0,
+            start: 0,
+            in_string: false,
+            escaped: false,
+            depth: 0,
+            json: JsonState::BeforeArray,
+        }
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+        self.buf.extend_from_slice(chunk);
+        let mut records = Vec::new();
+        while self.scanned < self.buf.len() {
+            let i = self.scanned;
+            let byte = self.buf[i];
+            self.scanned += 1;
+            match self.format {
+                BulkFormat::Ndjson => {
+                    if byte == b'\n' {
+                        self.emit_line(i, &mut records);
+                    }
+                }
+                BulkFormat::Csv => match byte {
+                    b'"' => self.in_string = !self.in_string,
+                    b'\n' if !self.in_string => self.emit_line(i, &mut records),
+                    _ => {}
+                },
+                BulkFormat::Json => self.scan_json(byte, i, &mut records)?,
+            }
+        }
+
+        // Byte yang sudah menjadi record tidak diperlukan lagi.
+        self.buf.drain(..self.start);
+        self.scanned -= self.start;
+        self.start = 0;
+        if self.buf.len() > MAX_RECORD_BYTES {
+            return Err(format!("Satu record melebihi {} byte", MAX_RECORD_BYTES));
+        }
+        Ok(records)
+    }
+
+    // Record terakhir yang tidak diakhiri newline.
+    pub fn finish(self) -> Result<Option<Vec<u8>>, String> {
+        match self.format {
+            BulkFormat::Json if self.json != JsonState::AfterArray => Err("Array JSON tidak ditutup".to_string()),
+            BulkFormat::Json => Ok(None),
+            BulkFormat::Csv if self.in_string => Err("Kutip CSV tidak ditutup".to_string()),
+            BulkFormat::Csv | BulkFormat::Ndjson => Ok(line(&self.buf[self.start..]).map(<[u8]>::to_vec)),
+        }
+    }
+
+    fn emit_line(&mut self, end: usize, records: &mut Vec<Vec<u8>>) {
+        if let Some(record) = line(&self.buf[self.start..end]) {
+            records.push(record.to_vec());
+        }
+        self.start = end + 1;
+    }
+
+    fn scan_json(&mut self, byte: u8, i: usize, records: &mut Vec<Vec<u8>>) -> Result<(), String> {
+        match self.json {
+            JsonState::BeforeArray | JsonState::AfterArray if byte.is_ascii_whitespace() => self.start = i + 1,
+            JsonState::BeforeArray if byte == b'[' => {
+                self.json = JsonState::BetweenItems;
+                self.start = i + 1;
+            }
+            JsonState::BeforeArray => return Err("Body JSON harus berupa array".to_string()),
+            JsonState::AfterArray => return Err("Ada data setelah akhir array JSON".to_string()),
+            JsonState::BetweenItems => match byte {
+                b'{' => {
+                    self.json = JsonState::InItem;
+                    self.depth = 1;
+                    self.start = i;
+                }
+                b']' => {
+                    self.json = JsonState::AfterArray;
+                    self.start = i + 1;
+                }
+                b',' => self.start = i + 1,
+                _ if byte.is_ascii_whitespace() => self.start = i + 1,
+                _ => return Err("Setiap elemen array JSON harus berupa objek".to_string()),
+            },
+            JsonState::InItem if self.in_string => {
+                if self.escaped {
+                    self.escaped = false;
+                } else if byte == b'\\' {
+                    self.escaped = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                }
+            }
+            JsonState::InItem => match byte {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        records.push(self.buf[self.start..=i].to_vec());
+                        self.json = JsonState::BetweenItems;
+                        self.start = i + 1;
+                    }
+                }
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+}
+
+// Baris tanpa `\r` penutup; baris kosong dilewati.
+fn line(bytes: &[u8]) -> Option<&[u8]> {
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    (!bytes.trim_ascii().is_empty()).then_some(bytes)
+}
+
+// Menulis buku satu per satu dalam format export. Header CSV ditulis bersama baris pertama.
+pub struct ExportEncoder {
+    format: BulkFormat,
+    first: bool,
+}
+
+impl ExportEncoder {
+    pub fn new(format: BulkFormat) -> Self {
+        Self { format, first: true }
+    }
+
+    pub fn start(&self) -> &'static [u8] {
+        if self.format == BulkFormat::Json { b"[" } else { b"" }
+    }
+
+    pub fn end(&self) -> &'static [u8] {
+        if self.format == BulkFormat::Json { b"]\n" } else { b"" }
+    }
+
+    pub fn write(&mut self, out: &mut Vec<u8>, book: &Book) -> anyhow::Result<()> {
+        match self.format {
+            BulkFormat::Json => {
+                if !self.first {
+                    out.push(b',');
+                }
+                serde_json::to_writer(&mut *out, book)?;
+            }
+            BulkFormat::Ndjson => {
+                serde_json::to_writer(&mut *out, book)?;
+                out.push(b'\n');
+            }
+            BulkFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new().has_headers(self.first).from_writer(&mut *out);
+                writer.serialize(book)?;
+                writer.flush()?;
+            }
+        }
+        self.first = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(format: BulkFormat, chunks: &[&str]) -> Result<Vec<String>, String> {
+        let mut splitter = RecordSplitter::new(format);
+        let mut records = Vec::new();
+        for chunk in chunks {
+            records.extend(splitter.push(chunk.as_bytes())?);
+        }
+        records.extend(splitter.finish()?);
+        Ok(records.into_iter().map(|r| String::from_utf8(r).unwrap()).collect())
+    }
+
+    #[test]
+    fn splits_csv_rows_across_chunks_and_quoted_newlines() {
+        let records = split(BulkFormat::Csv, &["title,author\r\n\"A, ", "\nB\",X\n\n", "C,Y"]).unwrap();
+        assert_eq!(records, ["title,author", "\"A, \nB\",X", "C,Y"]);
+    }
+
+    #[test]
+    fn splits_ndjson_lines() {
+        let records = split(BulkFormat::Ndjson, &["{\"a\":1}\n{\"a\"", ":2}\n"]).unwrap();
+        assert_eq!(records, ["{\"a\":1}", "{\"a\":2}"]);
+    }
+
+    #[test]
+    fn splits_json_array_elements() {
+        let records = split(BulkFormat::Json, &[" [{\"t\":\"}{\\\"\"}, ", "{\"n\":{\"x\":[1]}}]\n"]).unwrap();
+        assert_eq!(records, ["{\"t\":\"}{\\\"\"}", "{\"n\":{\"x\":[1]}}"]);
+    }
+
+    #[test]
+    fn rejects_malformed_json_bodies() {
+        assert!(split(BulkFormat::Json, &["{\"a\":1}"]).is_err());
+        assert!(split(BulkFormat::Json, &["[1, 2]"]).is_err());
+        assert!(split(BulkFormat::Json, &["[{\"a\":1}"]).is_err());
+    }
+}
@@ -14,16 +14,28 @@ pub enum AppError {
     DatabaseError(#[from] sqlx::Error),
     #[error("input tidak valid: {0}")]
     ValidationError(#[from] validator::ValidationErrors),
+    #[error("permintaan tidak valid: {0}")]
+    BadRequest(String),
+    #[error("tipe konten tidak didukung: {0}")]
+    UnsupportedMediaType(String),
     #[error("kredensial salah")]
     InvalidCredentials,
     #[error("token tidak valid atau kedaluwarsa")]
     InvalidToken,
     #[error("diperlukan otentikasi")]
     Unauthorized,
+    #[error("akses ditolak")]
+    Forbidden,
     #[error("tidak ditemukan: {0}")]
     NotFound(String),
     #[error("konflik: {0}")]
     Conflict(String),
+    #[error("prasyarat gagal: {0}")]
+    PreconditionFailed(String),
+    #[error("terlalu banyak permintaan: {0}")]
+    TooManyRequests(String),
+    #[error("layanan tidak tersedia: {0}")]
+    ServiceUnavailable(String),
 }
 
 impl IntoResponse for AppError {
@@ -38,11 +50,17 @@ impl IntoResponse for AppError {
                 (StatusCode::INTERNAL_SERVER_ERROR, "Kesalahan Database".to_string())
             }
             AppError::ValidationError(e) => (StatusCode::BAD_REQUEST, format!("Input tidak valid: {}", e)),
+            AppError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            AppError::UnsupportedMediaType(message) => (StatusCode::UNSUPPORTED_MEDIA_TYPE, message),
             AppError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "Kredensial salah".to_string()),
             AppError::InvalidToken => (StatusCode::UNAUTHORIZED, "Token tidak valid".to_string()),
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Diperlukan otentikasi".to_string()),
+            AppError::Forbidden => (StatusCode::FORBIDDEN, "Akses ditolak".to_string()),
             AppError::NotFound(entity) => (StatusCode::NOT_FOUND, format!("{} tidak ditemukan", entity)),
             AppError::Conflict(message) => (StatusCode::CONFLICT, message),
+            AppError::PreconditionFailed(message) => (StatusCode::PRECONDITION_FAILED, message),
+            AppError::TooManyRequests(message) => (StatusCode::TOO_MANY_REQUESTS, message),
+            AppError::ServiceUnavailable(message) => (StatusCode::SERVICE_UNAVAILABLE, message),
         };
         let body = Json(json!({ "error": error_message }));
         (status, body).into_response()
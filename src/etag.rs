@@ -0,0 +1,158 @@
+use crate::error::AppError;
+use axum::{
+    extract::FromRequestParts,
+    http::{
+        header::{ETAG, IF_MATCH, IF_NONE_MATCH},
+        request::Parts,
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::convert::Infallible;
+
+// ETag kuat untuk satu resource, diturunkan dari kolom `version` yang naik setiap update.
+pub fn etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+// Body JSON beserta header `ETag`.
+pub fn with_etag<T: Serialize>(version: i64, body: T) -> Response {
+    ([(ETAG, etag(version))], Json(body)).into_response()
+}
+
+// Daftar entity tag pada `If-Match` / `If-None-Match`.
+enum EntityTags {
+    Any,
+    List(Vec<String>),
+}
+
+impl EntityTags {
+    fn parse(headers: &HeaderMap, name: impl axum::http::header::AsHeaderName) -> Option<Self> {
+        let mut tags = Vec::new();
+        for value in headers.get_all(name) {
+            let value = value.to_str().unwrap_or_default();
+            if value.trim() == "*" {
+                return Some(Self::Any);
+            }
+            tags.extend(value.split(',').map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()));
+        }
+        (!tags.is_empty()).then_some(Self::List(tags))
+    }
+}
+
+// Header kondisional (RFC 9110) pada request ke satu resource.
+pub struct Preconditions {
+    if_match: Option<EntityTags>,
+    if_none_match: Option<EntityTags>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Preconditions {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            if_match: EntityTags::parse(&parts.headers, IF_MATCH),
+            if_none_match: EntityTags::parse(&parts.headers, IF_NONE_MATCH),
+        })
+    }
+}
+
+impl Preconditions {
+    // Versi yang diterima `If-Match` untuk dicek di klausa WHERE; `None` berarti tanpa
+    // syarat (header tidak ada atau `*`). Perbandingan kuat: tag lemah tidak pernah cocok.
+    pub fn expected_versions(&self) -> Option<Vec<i64>> {
+        match &self.if_match {
+            None | Some(EntityTags::Any) => None,
+            Some(EntityTags::List(tags)) => Some(tags.iter().filter_map(|tag| parse_strong(tag)).collect()),
+        }
+    }
+
+    // Respons `304 Not Modified` jika `If-None-Match` cocok dengan versi saat ini.
+    // Perbandingan lemah, jadi awalan `W/` diabaikan.
+    pub fn not_modified(&self, version: i64) -> Option<Response> {
+        let matched = match &self.if_none_match {
+            None => false,
+            Some(EntityTags::Any) => true,
+            Some(EntityTags::List(tags)) => tags
+                .iter()
+                .any(|tag| parse_strong(tag.strip_prefix("W/").unwrap_or(tag)) == Some(version)),
+        };
+        matched.then(|| (StatusCode::NOT_MODIFIED, [(ETAG, etag(version))]).into_response())
+    }
+}
+
+fn parse_strong(tag: &str) -> Option<i64> {
+    tag.strip_prefix('"')?.strip_suffix('"')?.parse().ok()
+}
+
+// Dipanggil ketika UPDATE/DELETE bersyarat tidak mengenai baris apa pun: resource
+// masih ada berarti versinya tidak cocok (412), selain itu memang tidak ditemukan.
+pub fn missed(exists: bool, entity: &str) -> AppError {
+    if exists {
+        AppError::PreconditionFailed(format!("{} telah diubah; ambil versi terbaru lalu ulangi", entity))
+    } else {
+        AppError::NotFound(entity.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn preconditions(if_match: &[&str], if_none_match: &[&str]) -> Preconditions {
+        let mut headers = HeaderMap::new();
+        for value in if_match {
+            headers.append(IF_MATCH, HeaderValue::from_str(value).unwrap());
+        }
+        for value in if_none_match {
+            headers.append(IF_NONE_MATCH, HeaderValue::from_str(value).unwrap());
+        }
+        Preconditions { if_match: EntityTags::parse(&headers, IF_MATCH), if_none_match: EntityTags::parse(&headers, IF_NONE_MATCH) }
+    }
+
+    #[test]
+    fn parses_any_and_comma_separated_lists_across_headers() {
+        let mut headers = HeaderMap::new();
+        assert!(EntityTags::parse(&headers, IF_MATCH).is_none());
+        headers.append(IF_MATCH, HeaderValue::from_static(" \"1\" , ,\"2\""));
+        headers.append(IF_MATCH, HeaderValue::from_static("W/\"3\""));
+        let Some(EntityTags::List(tags)) = EntityTags::parse(&headers, IF_MATCH) else { panic!("harus berupa daftar") };
+        assert_eq!(tags, ["\"1\"", "\"2\"", "W/\"3\""]);
+
+        headers.append(IF_MATCH, HeaderValue::from_static(" * "));
+        assert!(matches!(EntityTags::parse(&headers, IF_MATCH), Some(EntityTags::Any)));
+        headers.insert(IF_MATCH, HeaderValue::from_static(" , "));
+        assert!(EntityTags::parse(&headers, IF_MATCH).is_none());
+    }
+
+    #[test]
+    fn if_match_uses_strong_comparison() {
+        assert_eq!(preconditions(&[], &[]).expected_versions(), None);
+        assert_eq!(preconditions(&["*"], &[]).expected_versions(), None);
+        assert_eq!(preconditions(&["\"4\", \"7\""], &[]).expected_versions(), Some(vec![4, 7]));
+        // Tag lemah atau rusak tidak pernah cocok, sehingga UPDATE bersyarat gagal dengan 412.
+        assert_eq!(preconditions(&["W/\"4\", \"x\", 5"], &[]).expected_versions(), Some(vec![]));
+    }
+
+    #[test]
+    fn if_none_match_uses_weak_comparison() {
+        assert!(preconditions(&[], &[]).not_modified(3).is_none());
+        assert!(preconditions(&[], &["\"2\""]).not_modified(3).is_none());
+        let response = preconditions(&[], &["\"1\", W/\"3\""]).not_modified(3).unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[ETAG], "\"3\"");
+        assert!(preconditions(&[], &["*"]).not_modified(9).is_some());
+    }
+
+    #[test]
+    fn parse_strong_accepts_only_quoted_versions() {
+        assert_eq!(parse_strong("\"12\""), Some(12));
+        assert_eq!(parse_strong("W/\"12\""), None);
+        assert_eq!(parse_strong("12"), None);
+        assert_eq!(parse_strong("\"12"), None);
+        assert_eq!(parse_strong("\"abc\""), None);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::ws::{codec::EncodedEvent, envelope::EventEnvelope};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgListener, PgPool};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+pub type SharedEvent = Arc<EncodedEvent<EventEnvelope>>;
+pub type EventReceiver = broadcast::Receiver<SharedEvent>;
+
+const CHANNEL_CAPACITY: usize = 100;
+
+// Sumber event untuk semua koneksi realtime. Handler hanya menerbitkan lewat trait ini,
+// sehingga implementasinya bisa lokal (satu proses) atau lintas replika.
+#[async_trait]
+pub trait EventBus: Send + Sync {
+    // Error berarti event belum tentu sampai ke replika lain, sehingga pemanggil
+    // (misalnya relay outbox) bisa mencobanya lagi.
+    async fn publish(&self, event: EventEnvelope) -> anyhow::Result<()>;
+    fn subscribe(&self) -> EventReceiver;
+}
+
+pub struct InMemoryEventBus {
+    tx: broadcast::Sender<SharedEvent>,
+}
+
+impl InMemoryEventBus {
+    pub fn new() -> Self {
+        let (tx, _rx) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { tx }
+    }
+
+    fn deliver(&self, event: EventEnvelope) {
+        // Error hanya berarti belum ada subscriber, jadi cukup dicatat.
+        if let Err(e) = self.tx.send(Arc::new(EncodedEvent::new(event))) {
+            tracing::debug!("Tidak ada penerima event: {}", e);
+        }
+    }
+}
+
+impl Default for InMemoryEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl EventBus for InMemoryEventBus {
+    async fn publish(&self, event: EventEnvelope) -> anyhow::Result<()> {
+        self.deliver(event);
+        Ok(())
+    }
+
+    fn subscribe(&self) -> EventReceiver {
+        self.tx.subscribe()
+    }
+}
+
+const NOTIFY_CHANNEL: &str = "ws_events";
+// Batas payload NOTIFY di Postgres adalah 8000 byte; sisakan ruang untuk pembungkusnya.
+const MAX_INLINE_PAYLOAD: usize = 7000;
+const PAYLOAD_RETENTION_MINUTES: i32 = 10;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Notification {
+    Inline(Box<EventEnvelope>),
+    Ref(Uuid),
+}
+
+// Menyebarkan event ke semua replika lewat LISTEN/NOTIFY. Event yang terlalu besar
+// disimpan di tabel `ws_event_payloads` dan hanya id-nya yang dikirim lewat NOTIFY.
+// Replika ini juga menerima NOTIFY miliknya sendiri, sehingga urutan pengiriman
+// lokal sama dengan replika lain.
+pub struct PgEventBus {
+    pool: PgPool,
+    local: InMemoryEventBus,
+}
+
+impl PgEventBus {
+    pub async fn start(pool: PgPool) -> Result<Arc<Self>, sqlx::Error> {
+        let mut listener = PgListener::connect_with(&pool).await?;
+        listener.listen(NOTIFY_CHANNEL).await?;
+
+        let bus = Arc::new(Self { pool, local: InMemoryEventBus::new() });
+
+        let listener_bus = bus.clone();
+        tokio::spawn(async move {
+            loop {
+                match listener.recv().await {
+                    Ok(notification) => listener_bus.handle_notification(notification.payload()).await,
+                    Err(e) => {
+                        tracing::error!("Listener event Postgres gagal: {}", e);
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                }
+            }
+        });
+
+        let cleanup_pool = bus.pool.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                if let Err(e) = sqlx::query!(
+                    "DELETE FROM ws_event_payloads WHERE created_at < NOW() - make_interval(mins => $1)",
+                    PAYLOAD_RETENTION_MINUTES
+                )
+                .execute(&cleanup_pool)
+                .await
+                {
+                    tracing::warn!("Gagal membersihkan payload event lama: {}", e);
+                }
+            }
+        });
+
+        Ok(bus)
+    }
+
+    async fn handle_notification(&self, payload: &str) {
+        let notification = match serde_json::from_str::<Notification>(payload) {
+            Ok(notification) => notification,
+            Err(e) => {
+                tracing::error!("Payload NOTIFY tidak valid: {}", e);
+                return;
+            }
+        };
+        let event = match notification {
+            Notification::Inline(event) => *event,
+            Notification::Ref(id) => match self.load_payload(id).await {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::error!("Gagal memuat payload event {}: {:?}", id, e);
+                    return;
+                }
+            },
+        };
+        self.local.deliver(event);
+    }
+
+    async fn load_payload(&self, id: Uuid) -> anyhow::Result<EventEnvelope> {
+        let row = sqlx::query!("SELECT payload FROM ws_event_payloads WHERE id = $1", id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(serde_json::from_value(row.payload)?)
+    }
+
+    async fn notify(&self, event: EventEnvelope) -> anyhow::Result<()> {
+        let inline = serde_json::to_string(&Notification::Inline(Box::new(event.clone())))?;
+        let payload = if inline.len() <= MAX_INLINE_PAYLOAD {
+            inline
+        } else {
+            let id = sqlx::query_scalar!(
+                "INSERT INTO ws_event_payloads (payload) VALUES ($1) RETURNING id",
+                serde_json::to_value(&event)?
+            )
+            .fetch_one(&self.pool)
+            .await?;
+            serde_json::to_string(&Notification::Ref(id))?
+        };
+        sqlx::query!("SELECT pg_notify($1, $2)", NOTIFY_CHANNEL, payload)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventBus for PgEventBus {
+    async fn publish(&self, event: EventEnvelope) -> anyhow::Result<()> {
+        self.notify(event).await
+    }
+
+    fn subscribe(&self) -> EventReceiver {
+        self.local.subscribe()
+    }
+}
+
+pub async fn create_event_bus(pool: &PgPool) -> Result<Arc<dyn EventBus>, sqlx::Error> {
+    let backend = std::env::var("EVENT_BUS").unwrap_or_else(|_| "memory".to_string());
+    match backend.as_str() {
+        "postgres" => {
+            tracing::info!("Menggunakan event bus Postgres LISTEN/NOTIFY");
+            Ok(PgEventBus::start(pool.clone()).await?)
+        }
+        _ => Ok(Arc::new(InMemoryEventBus::new())),
+    }
+}
@@ -0,0 +1,60 @@
+use crate::error::AppError;
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Query, Request},
+    http::{header::CONTENT_TYPE, request::Parts},
+};
+use serde::de::DeserializeOwned;
+use validator::Validate;
+
+// Seperti `Query<T>`, tetapi parameter yang tidak dikenal atau tidak valid ditolak
+// dengan format error yang sama seperti body JSON (`{"error": ...}`, 400).
+pub struct ValidatedQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| AppError::BadRequest(e.body_text()))?;
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
+
+pub const MERGE_PATCH_CONTENT_TYPE: &str = "application/merge-patch+json";
+
+// Body JSON Merge Patch (RFC 7396). Hanya menerima `Content-Type: application/merge-patch+json`
+// agar patch tidak tertukar dengan body `PUT` biasa.
+pub struct MergePatch<T>(pub T);
+
+impl<T, S> FromRequest<S> for MergePatch<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_merge_patch = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .is_some_and(|mime| mime.trim().eq_ignore_ascii_case(MERGE_PATCH_CONTENT_TYPE));
+        if !is_merge_patch {
+            return Err(AppError::UnsupportedMediaType(format!("Content-Type harus {}", MERGE_PATCH_CONTENT_TYPE)));
+        }
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(|e| AppError::BadRequest(e.body_text()))?;
+        let value: T = serde_json::from_slice(&body).map_err(|e| AppError::BadRequest(format!("Patch tidak valid: {}", e)))?;
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
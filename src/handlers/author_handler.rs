@@ -0,0 +1,597 @@
+use crate::{
+    auth::Claims,
+    error::AppError,
+    etag::{self, Preconditions},
+    extract::{MergePatch, ValidatedQuery},
+    handlers::book_handler::escape_like,
+    models::{
+        author::{Author, AuthorPatch, AuthorQuery, BookAuthor, BookAuthorInput, BookAuthors, CreateAuthor, MergeAuthors, UpdateAuthor, MAX_BOOK_AUTHORS},
+        book::Book,
+        pagination::{Cursor, Page, PageQuery},
+    },
+    outbox,
+    ws::{self, envelope::EventContext},
+    AppState,
+};
+use axum::{extract::{Path, Query, State}, response::Response, Extension, Json};
+use sqlx::{PgExecutor, Postgres, Transaction};
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+#[axum::debug_handler]
+pub async fn get_all_authors(State(state): State<Arc<AppState>>, ValidatedQuery(query): ValidatedQuery<AuthorQuery>, _claims: Extension<Claims>) -> Result<Json<Page<Author>>, AppError> {
+    let limit = query.limit();
+    let (cursor_at, cursor_id) = query.cursor()?.map(|c| (c.at, c.id)).unzip();
+    // Kata utuh lewat indeks teks penuh, atau potongan nama (misalnya "Tolk").
+    let pattern = query.q.as_deref().map(|q| format!("%{}%", escape_like(q)));
+    let authors = sqlx::query_as!(
+        Author,
+        "SELECT id, name, bio, created_at, updated_at FROM authors
+         WHERE ($1::text IS NULL OR search_vector @@ websearch_to_tsquery('simple', $1) OR name ILIKE $2)
+           AND ($3::timestamptz IS NULL OR (created_at, id) < ($3, $4::uuid))
+         ORDER BY created_at DESC, id DESC
+         LIMIT $5",
+        query.q,
+        pattern,
+        cursor_at,
+        cursor_id,
+        limit + 1
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+    Ok(Json(Page::new(authors, limit, |author| Cursor::new(author.created_at, author.id).encode())))
+}
+
+#[axum::debug_handler]
+pub async fn create_author(State(state): State<Arc<AppState>>, ctx: EventContext, Json(payload): Json<CreateAuthor>) -> Result<Json<Author>, AppError> {
+    payload.validate()?;
+    let mut tx = state.db_pool.begin().await?;
+    let author = sqlx::query_as!(
+        Author,
+        "INSERT INTO authors (name, bio) VALUES ($1, $2) RETURNING id, name, bio, created_at, updated_at",
+        payload.name.trim(),
+        payload.bio
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    outbox::enqueue(&mut tx, &ctx.envelope(ws::WsEvent::AuthorCreated(author.clone()))).await?;
+    tx.commit().await?;
+    state.outbox.wake();
+    Ok(Json(author))
+}
+
+#[axum::debug_handler]
+pub async fn get_author_by_id(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, _claims: Extension<Claims>) -> Result<Json<Author>, AppError> {
+    let author = sqlx::query_as!(Author, "SELECT id, name, bio, created_at, updated_at FROM authors WHERE id = $1", id)
+        .fetch_optional(&state.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Penulis".to_string()))?;
+    Ok(Json(author))
+}
+
+#[axum::debug_handler]
+pub async fn update_author(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, ctx: EventContext, Json(payload): Json<UpdateAuthor>) -> Result<Json<Author>, AppError> {
+    payload.validate()?;
+    Ok(Json(apply_author_patch(&state, id, &ctx, payload.into()).await?))
+}
+
+#[axum::debug_handler]
+pub async fn patch_author(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, ctx: EventContext, MergePatch(patch): MergePatch<AuthorPatch>) -> Result<Json<Author>, AppError> {
+    Ok(Json(apply_author_patch(&state, id, &ctx, patch).await?))
+}
+
+// Satu UPDATE seperti `apply_email_patch`; CTE `old` menyimpan nilai lama untuk diff.
+async fn apply_author_patch(state: &AppState, id: Uuid, ctx: &EventContext, patch: AuthorPatch) -> Result<Author, AppError> {
+    // `null` untuk nama sudah ditolak validasi, jadi `None` berarti tidak diubah.
+    let name = patch.name.into_change().flatten();
+    let bio = patch.bio.into_change();
+
+    let mut tx = state.db_pool.begin().await?;
+    let row = sqlx::query!(
+        r#"WITH old AS (
+               SELECT id, name, bio, updated_at FROM authors WHERE id = $4 FOR UPDATE
+           )
+           UPDATE authors a
+           SET name = COALESCE($1, a.name),
+               bio = CASE WHEN $2 THEN $3 ELSE a.bio END,
+               updated_at = NOW()
+           FROM old
+           WHERE a.id = old.id
+           RETURNING a.id, a.name, a.bio, a.created_at, a.updated_at,
+                     old.name AS old_name, old.bio AS old_bio, old.updated_at AS old_updated_at"#,
+        name.as_deref().map(str::trim),
+        bio.is_some(),
+        bio.flatten(),
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Penulis".to_string()))?;
+
+    let author = Author {
+        id: row.id,
+        name: row.name,
+        bio: row.bio,
+        created_at: row.created_at,
+        updated_at: row.updated_at,
+    };
+    let old = Author { name: row.old_name, bio: row.old_bio, updated_at: row.old_updated_at, ..author.clone() };
+    let changes = ws::diff::diff(&old, &author);
+    outbox::enqueue(&mut tx, &ctx.envelope(ws::WsEvent::AuthorUpdated(author.clone())).with_changes(changes)).await?;
+    tx.commit().await?;
+    state.outbox.wake();
+    Ok(author)
+}
+
+// Penulis dipakai bersama oleh banyak buku, jadi hanya admin yang boleh menghapus, dan
+// hanya jika sudah tidak terhubung dengan buku mana pun.
+#[axum::debug_handler]
+pub async fn delete_author(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>, ctx: EventContext) -> Result<(), AppError> {
+    claims.require_admin()?;
+    let mut tx = state.db_pool.begin().await?;
+    let result = sqlx::query!("DELETE FROM authors WHERE id = $1", id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            if let sqlx::Error::Database(db_err) = &e
+                && db_err.is_foreign_key_violation()
+            {
+                return AppError::Conflict("Penulis masih terhubung dengan buku".to_string());
+            }
+            AppError::DatabaseError(e)
+        })?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Penulis".to_string()));
+    }
+    outbox::enqueue(&mut tx, &ctx.envelope(ws::WsEvent::AuthorDeleted(id))).await?;
+    tx.commit().await?;
+    state.outbox.wake();
+    Ok(())
+}
+
+// Menggabungkan penulis duplikat ke penulis `{id}`, misalnya "Tolkien, J.R.R." dan
+// "J. R. R. Tolkien" yang oleh migrasi dianggap berbeda. Relasi buku dipindahkan dengan
+// urutan tetap (peran yang jadi ganda disatukan), lalu penulis sumber dihapus. Nama dan
+// bio penulis tujuan tidak diubah.
+#[axum::debug_handler]
+pub async fn merge_authors(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>, ctx: EventContext, Json(payload): Json<MergeAuthors>) -> Result<Json<Author>, AppError> {
+    claims.require_admin()?;
+    payload.validate()?;
+    let sources: Vec<Uuid> = payload.source_ids.into_iter().filter(|source| *source != id).collect::<HashSet<_>>().into_iter().collect();
+    if sources.is_empty() {
+        return Err(AppError::BadRequest("Penulis tidak bisa digabung dengan dirinya sendiri".to_string()));
+    }
+
+    let mut tx = state.db_pool.begin().await?;
+    let locked = sqlx::query_scalar!("SELECT id FROM authors WHERE id = $1 OR id = ANY($2) ORDER BY id FOR UPDATE", id, &sources)
+        .fetch_all(&mut *tx)
+        .await?;
+    if !locked.contains(&id) {
+        return Err(AppError::NotFound("Penulis".to_string()));
+    }
+    if locked.len() != sources.len() + 1 {
+        return Err(AppError::BadRequest("Ada penulis yang tidak ditemukan".to_string()));
+    }
+    let book_ids = sqlx::query_scalar!(
+        "SELECT id FROM books WHERE id IN (SELECT book_id FROM book_authors WHERE author_id = ANY($1)) ORDER BY id FOR UPDATE",
+        &sources
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // Daftar baru tiap buku: sumber diganti tujuan, duplikat (penulis, peran) hanya
+    // disimpan pada posisi pertamanya, lalu posisi dinomori ulang.
+    let rows = sqlx::query!(
+        r#"SELECT book_id AS "book_id!", author_id AS "author_id!", role AS "role!",
+                  (row_number() OVER (PARTITION BY book_id ORDER BY position) - 1)::int AS "position!"
+           FROM (SELECT DISTINCT ON (book_id, author_id, role) book_id, author_id, role, position
+                 FROM (SELECT book_id, CASE WHEN author_id = ANY($2) THEN $1 ELSE author_id END AS author_id, role, position
+                       FROM book_authors WHERE book_id = ANY($3)) replaced
+                 ORDER BY book_id, author_id, role, position) deduped"#,
+        id,
+        &sources,
+        &book_ids
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    sqlx::query!("DELETE FROM book_authors WHERE book_id = ANY($1)", &book_ids)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!(
+        "INSERT INTO book_authors (book_id, author_id, role, position)
+         SELECT * FROM UNNEST($1::uuid[], $2::uuid[], $3::text[], $4::int[])",
+        &rows.iter().map(|row| row.book_id).collect::<Vec<_>>(),
+        &rows.iter().map(|row| row.author_id).collect::<Vec<_>>(),
+        &rows.iter().map(|row| row.role.clone()).collect::<Vec<_>>(),
+        &rows.iter().map(|row| row.position).collect::<Vec<_>>()
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!("UPDATE books SET version = version + 1, updated_at = NOW() WHERE id = ANY($1)", &book_ids)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!("DELETE FROM authors WHERE id = ANY($1)", &sources)
+        .execute(&mut *tx)
+        .await?;
+
+    let author = sqlx::query_as!(Author, "SELECT id, name, bio, created_at, updated_at FROM authors WHERE id = $1", id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let mut events = Vec::with_capacity(book_ids.len() + sources.len());
+    for book_id in book_ids {
+        let authors = load_book_authors(&mut *tx, book_id).await?;
+        events.push(ws::WsEvent::BookAuthorsUpdated(BookAuthors { book_id, authors }));
+    }
+    events.extend(sources.into_iter().map(ws::WsEvent::AuthorDeleted));
+    for event in events {
+        outbox::enqueue(&mut tx, &ctx.envelope(event)).await?;
+    }
+    tx.commit().await?;
+    state.outbox.wake();
+    Ok(Json(author))
+}
+
+// Buku (di luar tempat sampah) yang melibatkan penulis ini dalam peran apa pun.
+#[axum::debug_handler]
+pub async fn get_author_books(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Query(page): Query<PageQuery>, _claims: Extension<Claims>) -> Result<Json<Page<Book>>, AppError> {
+    let limit = page.limit();
+    let (cursor_at, cursor_id) = page.cursor()?.map(|c| (c.at, c.id)).unzip();
+    let exists = sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM authors WHERE id = $1)", id)
+        .fetch_one(&state.db_pool)
+        .await?;
+    if exists != Some(true) {
+        return Err(AppError::NotFound("Penulis".to_string()));
+    }
+    let books = sqlx::query_as!(
+        Book,
+        "SELECT b.id, b.title, b.author, b.publication_year, b.isbn, b.created_at, b.updated_at, b.version, b.created_by, b.deleted_at
+         FROM books b
+         WHERE b.deleted_at IS NULL AND EXISTS (SELECT 1 FROM book_authors ba WHERE ba.book_id = b.id AND ba.author_id = $1)
+           AND ($2::timestamptz IS NULL OR (b.created_at, b.id) < ($2, $3::uuid))
+         ORDER BY b.created_at DESC, b.id DESC
+         LIMIT $4",
+        id,
+        cursor_at,
+        cursor_id,
+        limit + 1
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+    Ok(Json(Page::new(books, limit, |book| Cursor::new(book.created_at, book.id).encode())))
+}
+
+#[axum::debug_handler]
+pub async fn get_book_authors(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, _claims: Extension<Claims>) -> Result<Json<Vec<BookAuthor>>, AppError> {
+    let exists = sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM books WHERE id = $1 AND deleted_at IS NULL)", id)
+        .fetch_one(&state.db_pool)
+        .await?;
+    if exists != Some(true) {
+        return Err(AppError::NotFound("Buku".to_string()));
+    }
+    Ok(Json(load_book_authors(&state.db_pool, id).await?))
+}
+
+// Mengganti seluruh daftar penulis buku; urutan array menjadi `position`. Aturan
+// kepemilikan dan `If-Match` sama dengan mengubah buku, dan versi buku ikut naik.
+#[axum::debug_handler]
+pub async fn set_book_authors(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>, preconditions: Preconditions, ctx: EventContext, Json(payload): Json<Vec<BookAuthorInput>>) -> Result<Response, AppError> {
+    if payload.len() > MAX_BOOK_AUTHORS {
+        return Err(AppError::BadRequest(format!("Paling banyak {} penulis per buku", MAX_BOOK_AUTHORS)));
+    }
+    let mut seen = HashSet::new();
+    if !payload.iter().all(|entry| seen.insert((entry.author_id, entry.role))) {
+        return Err(AppError::BadRequest("Penulis dengan peran yang sama muncul lebih dari sekali".to_string()));
+    }
+
+    let mut tx = state.db_pool.begin().await?;
+    let book = sqlx::query!("SELECT created_by, version FROM books WHERE id = $1 AND deleted_at IS NULL FOR UPDATE", id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Buku".to_string()))?;
+    if !claims.can_modify(book.created_by) {
+        return Err(AppError::Forbidden);
+    }
+    if preconditions.expected_versions().is_some_and(|expected| !expected.contains(&book.version)) {
+        return Err(etag::missed(true, "Buku"));
+    }
+    replace_book_authors(&mut tx, id, &payload).await?;
+    let version = sqlx::query_scalar!("UPDATE books SET version = version + 1, updated_at = NOW() WHERE id = $1 RETURNING version", id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let authors = load_book_authors(&mut *tx, id).await?;
+    let event = ws::WsEvent::BookAuthorsUpdated(BookAuthors { book_id: id, authors: authors.clone() });
+    outbox::enqueue(&mut tx, &ctx.envelope(event)).await?;
+    tx.commit().await?;
+    state.outbox.wake();
+    Ok(etag::with_etag(version, authors))
+}
+
+// Menghubungkan buku baru dengan penulis sesuai teks `books.author`, dengan aturan
+// pencocokan yang sama seperti migrasi: spasi dirapikan dan huruf besar/kecil diabaikan.
+// Penulis yang belum ada dibuat dengan ejaan kemunculan pertamanya; jika ada beberapa
+// yang cocok, dipakai yang tertua.
+pub(crate) async fn link_authors_by_name(tx: &mut Transaction<'_, Postgres>, book_ids: &[Uuid], names: &[String]) -> Result<(), sqlx::Error> {
+    lock_author_names(tx, names).await?;
+    sqlx::query!(
+        r#"WITH input AS (
+               SELECT i.book_id, btrim(regexp_replace(i.name, '\s+', ' ', 'g')) AS name, i.ord
+               FROM UNNEST($1::uuid[], $2::text[]) WITH ORDINALITY AS i(book_id, name, ord)
+           ),
+           missing AS (
+               SELECT DISTINCT ON (lower(i.name)) i.name FROM input i
+               WHERE i.name <> '' AND NOT EXISTS (SELECT 1 FROM authors a WHERE lower(a.name) = lower(i.name))
+               ORDER BY lower(i.name), i.ord
+           ),
+           created AS (
+               INSERT INTO authors (name) SELECT name FROM missing RETURNING id, name, created_at
+           ),
+           candidates AS (
+               SELECT id, name, created_at FROM authors WHERE lower(name) IN (SELECT lower(name) FROM input)
+               UNION ALL
+               SELECT id, name, created_at FROM created
+           )
+           INSERT INTO book_authors (book_id, author_id, role, position)
+           SELECT DISTINCT ON (i.book_id) i.book_id, c.id, 'author', 0
+           FROM input i JOIN candidates c ON lower(c.name) = lower(i.name)
+           ORDER BY i.book_id, c.created_at, c.id"#,
+        book_ids,
+        names
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+// Ruang kunci advisory lock untuk nama penulis, terpisah dari kunci `bigint` relay dan webhook.
+const AUTHOR_NAME_LOCK_SPACE: i32 = 0x6175_7468;
+
+// Mengunci setiap nama (setelah dirapikan, tanpa membedakan huruf besar/kecil) sampai transaksi
+// selesai, agar dua transaksi yang bersamaan tidak sama-sama membuat penulis baru untuk nama yang
+// sama. Kunci diambil berurutan untuk menghindari deadlock; pencarian sesudahnya memakai snapshot
+// baru sehingga melihat penulis yang dibuat transaksi sebelumnya.
+async fn lock_author_names(tx: &mut Transaction<'_, Postgres>, names: &[String]) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"SELECT pg_advisory_xact_lock($1, hashtext(k))
+           FROM (SELECT DISTINCT lower(btrim(regexp_replace(n, '\s+', ' ', 'g'))) AS k FROM UNNEST($2::text[]) AS n) keys
+           WHERE k <> ''
+           ORDER BY k"#,
+        AUTHOR_NAME_LOCK_SPACE,
+        names
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+// Mengganti entri `author` pertama buku dengan penulis bernama `name` (dicari atau dibuat
+// seperti pada `link_authors_by_name`) setelah `books.author` diubah. Penulis lain dan peran
+// lain tidak disentuh. Mengembalikan daftar penulis yang baru, atau `None` jika tidak berubah.
+pub(crate) async fn relink_primary_author(tx: &mut Transaction<'_, Postgres>, book_id: Uuid, name: &str) -> Result<Option<Vec<BookAuthor>>, AppError> {
+    lock_author_names(tx, &[name.to_string()]).await?;
+    let author_id = sqlx::query_scalar!(
+        r#"WITH input AS (
+               SELECT btrim(regexp_replace($1, '\s+', ' ', 'g')) AS name
+           ),
+           existing AS (
+               SELECT a.id FROM authors a, input i WHERE lower(a.name) = lower(i.name)
+               ORDER BY a.created_at, a.id
+               LIMIT 1
+           ),
+           created AS (
+               INSERT INTO authors (name) SELECT name FROM input
+               WHERE name <> '' AND NOT EXISTS (SELECT 1 FROM existing)
+               RETURNING id
+           )
+           SELECT id AS "id!" FROM existing UNION ALL SELECT id FROM created"#,
+        name
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    let Some(author_id) = author_id else {
+        return Ok(None);
+    };
+
+    let primary = sqlx::query!(
+        "SELECT author_id, position FROM book_authors WHERE book_id = $1 AND role = 'author' ORDER BY position LIMIT 1",
+        book_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    if primary.as_ref().is_some_and(|primary| primary.author_id == author_id) {
+        return Ok(None);
+    }
+    // Penulis baru mungkin sudah tercantum sebagai penulis pendamping; entri itu dipindahkan
+    // ke posisi penulis utama agar tidak melanggar primary key.
+    sqlx::query!(
+        "DELETE FROM book_authors WHERE book_id = $1 AND role = 'author' AND (author_id = $2 OR position = $3)",
+        book_id,
+        author_id,
+        primary.as_ref().map(|primary| primary.position)
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO book_authors (book_id, author_id, role, position)
+         SELECT $1, $2, 'author', COALESCE($3, (SELECT COALESCE(MAX(position) + 1, 0) FROM book_authors WHERE book_id = $1))",
+        book_id,
+        author_id,
+        primary.map(|primary| primary.position)
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(Some(load_book_authors(&mut **tx, book_id).await?))
+}
+
+async fn replace_book_authors(tx: &mut Transaction<'_, Postgres>, book_id: Uuid, entries: &[BookAuthorInput]) -> Result<(), AppError> {
+    sqlx::query!("DELETE FROM book_authors WHERE book_id = $1", book_id)
+        .execute(&mut **tx)
+        .await?;
+    let author_ids: Vec<Uuid> = entries.iter().map(|entry| entry.author_id).collect();
+    let roles: Vec<String> = entries.iter().map(|entry| entry.role.as_str().to_string()).collect();
+    sqlx::query!(
+        "INSERT INTO book_authors (book_id, author_id, role, position)
+         SELECT $1, e.author_id, e.role, (e.ord - 1)::int
+         FROM UNNEST($2::uuid[], $3::text[]) WITH ORDINALITY AS e(author_id, role, ord)",
+        book_id,
+        &author_ids,
+        &roles
+    )
+    .execute(&mut **tx)
+    .await
+    .map_err(|e| {
+        if let sqlx::Error::Database(db_err) = &e
+            && db_err.is_foreign_key_violation()
+        {
+            return AppError::BadRequest("Ada penulis yang tidak ditemukan".to_string());
+        }
+        AppError::DatabaseError(e)
+    })?;
+    Ok(())
+}
+
+async fn load_book_authors<'e>(executor: impl PgExecutor<'e>, book_id: Uuid) -> Result<Vec<BookAuthor>, AppError> {
+    let rows = sqlx::query!(
+        "SELECT ba.author_id, a.name, ba.role, ba.position
+         FROM book_authors ba JOIN authors a ON a.id = ba.author_id
+         WHERE ba.book_id = $1
+         ORDER BY ba.position",
+        book_id
+    )
+    .fetch_all(executor)
+    .await?;
+    rows.into_iter()
+        .map(|row| {
+            let role = row.role.parse().map_err(|e: String| AppError::InternalServerError(anyhow::anyhow!(e)))?;
+            Ok(BookAuthor { author_id: row.author_id, name: row.name, role, position: row.position })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    async fn insert_book(tx: &mut Transaction<'_, Postgres>, author: &str) -> Uuid {
+        sqlx::query_scalar!("INSERT INTO books (title, author) VALUES ('Buku Uji', $1) RETURNING id", author)
+            .fetch_one(&mut **tx)
+            .await
+            .unwrap()
+    }
+
+    async fn linked_author(tx: &mut Transaction<'_, Postgres>, book_id: Uuid) -> Vec<(Uuid, String)> {
+        load_book_authors(&mut **tx, book_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|author| (author.author_id, author.name))
+            .collect()
+    }
+
+    // Dijalankan dalam transaksi yang di-rollback; dilewati jika `DATABASE_URL` tidak diatur.
+    #[tokio::test]
+    async fn links_new_books_to_existing_or_created_authors() {
+        let Ok(url) = std::env::var("DATABASE_URL") else { return };
+        let pool = PgPool::connect(&url).await.unwrap();
+        let mut tx = pool.begin().await.unwrap();
+        let name = format!("Penulis Uji {}", Uuid::new_v4());
+
+        let first = insert_book(&mut tx, &format!("  {}  ", name.replace(' ', "   "))).await;
+        let second = insert_book(&mut tx, &name.to_uppercase()).await;
+        let blank = insert_book(&mut tx, " ").await;
+        link_authors_by_name(&mut tx, &[first, second, blank], &[format!("  {}  ", name.replace(' ', "   ")), name.to_uppercase(), " ".to_string()])
+            .await
+            .unwrap();
+
+        let linked = linked_author(&mut tx, first).await;
+        assert_eq!(linked.len(), 1);
+        assert_eq!(linked[0].1, name);
+        assert_eq!(linked_author(&mut tx, second).await, linked);
+        assert!(linked_author(&mut tx, blank).await.is_empty());
+
+        // Buku berikutnya memakai penulis yang sudah ada, bukan membuat yang baru.
+        let third = insert_book(&mut tx, &name).await;
+        link_authors_by_name(&mut tx, &[third], std::slice::from_ref(&name)).await.unwrap();
+        assert_eq!(linked_author(&mut tx, third).await, linked);
+        let count = sqlx::query_scalar!("SELECT COUNT(*) FROM authors WHERE lower(name) = lower($1)", name)
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(count, Some(1));
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn relinks_only_the_primary_author_when_the_author_text_changes() {
+        let Ok(url) = std::env::var("DATABASE_URL") else { return };
+        let pool = PgPool::connect(&url).await.unwrap();
+        let mut tx = pool.begin().await.unwrap();
+        let name = format!("Penulis Uji {}", Uuid::new_v4());
+        let book = insert_book(&mut tx, &name).await;
+        link_authors_by_name(&mut tx, &[book], std::slice::from_ref(&name)).await.unwrap();
+        let translator = sqlx::query_scalar!("INSERT INTO authors (name) VALUES ($1) RETURNING id", format!("{name} (penerjemah)"))
+            .fetch_one(&mut *tx)
+            .await
+            .unwrap();
+        sqlx::query!("INSERT INTO book_authors (book_id, author_id, role, position) VALUES ($1, $2, 'translator', 1)", book, translator)
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+
+        // Hanya beda huruf besar/kecil: penulis yang sama, tidak ada perubahan.
+        assert!(relink_primary_author(&mut tx, book, &name.to_uppercase()).await.unwrap().is_none());
+
+        let renamed = format!("{name} Baru");
+        let authors = relink_primary_author(&mut tx, book, &renamed).await.unwrap().unwrap();
+        let linked: Vec<(String, i32)> = authors.into_iter().map(|author| (author.name, author.position)).collect();
+        assert_eq!(linked, [(renamed, 0), (format!("{name} (penerjemah)"), 1)]);
+        tx.rollback().await.unwrap();
+    }
+
+    // Transaksi kedua harus menunggu kunci nama dan memakai penulis yang dibuat transaksi
+    // pertama. Data uji dihapus di akhir karena kedua transaksi di-commit.
+    #[tokio::test]
+    async fn concurrent_links_create_a_single_author() {
+        let Ok(url) = std::env::var("DATABASE_URL") else { return };
+        let pool = PgPool::connect(&url).await.unwrap();
+        let name = format!("Penulis Uji {}", Uuid::new_v4());
+
+        let mut first = pool.begin().await.unwrap();
+        let first_book = insert_book(&mut first, &name).await;
+        link_authors_by_name(&mut first, &[first_book], std::slice::from_ref(&name)).await.unwrap();
+
+        let second = tokio::spawn({
+            let pool = pool.clone();
+            let name = name.to_lowercase();
+            async move {
+                let mut tx = pool.begin().await.unwrap();
+                let book = insert_book(&mut tx, &name).await;
+                link_authors_by_name(&mut tx, &[book], &[name]).await.unwrap();
+                tx.commit().await.unwrap();
+                book
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(!second.is_finished());
+        first.commit().await.unwrap();
+        let second_book = second.await.unwrap();
+
+        let count = sqlx::query_scalar!("SELECT COUNT(*) FROM authors WHERE lower(name) = lower($1)", name)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, Some(1));
+
+        sqlx::query!("DELETE FROM books WHERE id = ANY($1)", &[first_book, second_book])
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query!("DELETE FROM authors WHERE lower(name) = lower($1)", name)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
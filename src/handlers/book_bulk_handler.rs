@@ -0,0 +1,459 @@
+use crate::{
+    auth::Claims,
+    bulk::{BulkFormat, ExportEncoder, RecordSplitter},
+    error::AppError,
+    extract::ValidatedQuery,
+    models::{
+        book::{Book, CreateBook},
+        bulk::{BookImportSummary, ExportQuery, ImportQuery, ImportReport},
+        isbn,
+    },
+    handlers::author_handler,
+    outbox,
+    ws::{self, envelope::EventContext},
+    AppState,
+};
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderMap,
+    },
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use futures_util::{stream, StreamExt};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+use validator::Validate;
+
+// Potongan export dikirim setelah sekitar sebanyak ini byte terkumpul.
+const EXPORT_FLUSH_BYTES: usize = 64 * 1024;
+
+// Import massal dari CSV (baris pertama header), array JSON atau NDJSON. Body dibaca
+// per potongan; baris yang valid disimpan per chunk dalam transaksi sendiri, sehingga
+// chunk yang sudah di-commit tetap tersimpan walau body terputus atau database gagal
+// di tengah. Dalam kedua kasus laporan tetap dikembalikan dengan `aborted` terisi.
+// Satu event `BooksImported` untuk seluruh import, bukan `BookCreated` per baris.
+#[axum::debug_handler]
+pub async fn import_books(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<Claims>,
+    ctx: EventContext,
+    ValidatedQuery(query): ValidatedQuery<ImportQuery>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Json<ImportReport>, AppError> {
+    let format = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(BulkFormat::from_content_type)
+        .ok_or_else(|| AppError::UnsupportedMediaType("Content-Type harus text/csv, application/json atau application/x-ndjson".to_string()))?;
+    let created_by = claims.user_id().ok_or(AppError::InvalidToken)?;
+
+    let mut importer = Importer::new(&state.db_pool, created_by, query.dry_run, query.chunk_size());
+    let mut parser = RowParser::new(format);
+    let mut splitter = RecordSplitter::new(format);
+    let mut chunks = body.into_data_stream();
+    while let Some(chunk) = chunks.next().await {
+        let records = chunk
+            .map_err(|e| format!("Gagal membaca body: {}", e))
+            .and_then(|chunk| splitter.push(&chunk));
+        match records {
+            Ok(records) => {
+                for record in records {
+                    if let Some(row) = parser.parse(&record) {
+                        importer.add(row).await;
+                    }
+                }
+            }
+            Err(message) => importer.report.aborted = Some(message),
+        }
+        if importer.report.aborted.is_some() {
+            break;
+        }
+    }
+    if importer.report.aborted.is_none() {
+        match splitter.finish() {
+            Ok(Some(record)) => {
+                if let Some(row) = parser.parse(&record) {
+                    importer.add(row).await;
+                }
+            }
+            Ok(None) => {}
+            Err(message) => importer.report.aborted = Some(message),
+        }
+    }
+    let report = importer.finish(&ctx).await;
+    if !report.dry_run && report.imported > 0 {
+        state.outbox.wake();
+    }
+    Ok(Json(report))
+}
+
+// Mengubah record mentah menjadi `CreateBook`. Untuk CSV, record pertama adalah header.
+enum RowParser {
+    Csv { headers: Option<csv::StringRecord> },
+    Json,
+}
+
+impl RowParser {
+    fn new(format: BulkFormat) -> Self {
+        match format {
+            BulkFormat::Csv => Self::Csv { headers: None },
+            BulkFormat::Json | BulkFormat::Ndjson => Self::Json,
+        }
+    }
+
+    // `None` untuk baris header CSV.
+    fn parse(&mut self, record: &[u8]) -> Option<Result<CreateBook, String>> {
+        match self {
+            Self::Json => Some(serde_json::from_slice(record).map_err(|e| format!("JSON tidak valid: {}", e))),
+            Self::Csv { headers } => {
+                let fields = csv::ReaderBuilder::new()
+                    .has_headers(false)
+                    .from_reader(record)
+                    .records()
+                    .next()?
+                    .map_err(|e| format!("CSV tidak valid: {}", e));
+                let fields = match fields {
+                    Ok(fields) => fields,
+                    Err(message) => return Some(Err(message)),
+                };
+                let Some(headers) = headers else {
+                    *headers = Some(fields.iter().map(str::trim).collect());
+                    return None;
+                };
+                Some(fields.deserialize(Some(headers)).map_err(|e| format!("Baris tidak valid: {}", e)))
+            }
+        }
+    }
+}
+
+struct Importer<'a> {
+    pool: &'a PgPool,
+    created_by: Uuid,
+    chunk_size: usize,
+    pending: Vec<(usize, CreateBook)>,
+    report: ImportReport,
+    summary_enqueued: bool,
+}
+
+impl<'a> Importer<'a> {
+    fn new(pool: &'a PgPool, created_by: Uuid, dry_run: bool, chunk_size: usize) -> Self {
+        Self {
+            pool,
+            created_by,
+            chunk_size,
+            pending: Vec::with_capacity(chunk_size),
+            report: ImportReport { dry_run, ..Default::default() },
+            summary_enqueued: false,
+        }
+    }
+
+    // Baris sesudah import dihentikan tidak diproses lagi.
+    async fn add(&mut self, row: Result<CreateBook, String>) {
+        if self.report.aborted.is_some() {
+            return;
+        }
+        self.report.total += 1;
+        let number = self.report.total;
+        let book = row.and_then(|book| match book.validate() {
+            Ok(()) => Ok(book),
+            Err(e) => Err(format!("Input tidak valid: {}", e)),
+        });
+        match book {
+            Ok(book) => {
+                self.pending.push((number, book));
+                if self.pending.len() >= self.chunk_size {
+                    self.flush(None).await;
+                }
+            }
+            Err(message) => self.report.reject(number, message),
+        }
+    }
+
+    // Menyimpan satu chunk dengan satu INSERT. Baris dengan ISBN yang sudah ada (di
+    // database atau lebih awal di chunk yang sama) dilewati dan dilaporkan. Pada dry run
+    // transaksi di-rollback, sehingga duplikat antar-chunk tidak terdeteksi. Jika database
+    // gagal, seluruh baris chunk ditolak dan import dihentikan. `summary` diisi untuk chunk
+    // terakhir agar event ringkasan ikut di-commit bersama chunk tersebut.
+    async fn flush(&mut self, summary: Option<&EventContext>) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let rows = std::mem::take(&mut self.pending);
+        let isbns: Vec<Option<String>> = rows.iter().map(|(_, book)| book.isbn.as_deref().and_then(isbn::normalize)).collect();
+        let inserted = match self.insert_chunk(&rows, &isbns, summary).await {
+            Ok(inserted) => inserted,
+            Err(e) => {
+                tracing::error!("Import buku gagal menyimpan chunk: {:?}", e);
+                for (number, _) in &rows {
+                    self.report.reject(*number, "Chunk gagal disimpan".to_string());
+                }
+                self.report.aborted = Some(format!("Gagal menyimpan baris {}-{}", rows[0].0, rows[rows.len() - 1].0));
+                return;
+            }
+        };
+
+        // Baris tanpa ISBN tidak pernah bentrok; untuk ISBN yang sama hanya baris pertama yang masuk.
+        let mut inserted_isbns: HashSet<String> = inserted.into_iter().collect();
+        for ((number, _), isbn) in rows.into_iter().zip(isbns) {
+            match isbn {
+                Some(isbn) if !inserted_isbns.remove(&isbn) => {
+                    self.report.reject(number, format!("ISBN {} sudah dipakai buku lain", isbn))
+                }
+                _ => self.report.imported += 1,
+            }
+        }
+    }
+
+    // ISBN dari baris yang benar-benar tersimpan. Buku baru langsung dihubungkan dengan
+    // penulisnya seperti pada `create_book`.
+    async fn insert_chunk(&mut self, rows: &[(usize, CreateBook)], isbns: &[Option<String>], summary: Option<&EventContext>) -> Result<Vec<String>, AppError> {
+        let titles: Vec<String> = rows.iter().map(|(_, book)| book.title.clone()).collect();
+        let authors: Vec<String> = rows.iter().map(|(_, book)| book.author.clone()).collect();
+        let years: Vec<Option<i32>> = rows.iter().map(|(_, book)| book.publication_year).collect();
+
+        let mut tx = self.pool.begin().await?;
+        let inserted = sqlx::query!(
+            r#"INSERT INTO books (title, author, publication_year, isbn, created_by)
+               SELECT r.title, r.author, r.publication_year, r.isbn, $5
+               FROM UNNEST($1::text[], $2::text[], $3::int4[], $4::text[])
+                    WITH ORDINALITY AS r(title, author, publication_year, isbn, ord)
+               ORDER BY r.ord
+               ON CONFLICT (isbn) DO NOTHING
+               RETURNING id, author, isbn"#,
+            &titles,
+            &authors,
+            &years as &[Option<i32>],
+            isbns as &[Option<String>],
+            self.created_by
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let book_ids: Vec<Uuid> = inserted.iter().map(|row| row.id).collect();
+        let names: Vec<String> = inserted.iter().map(|row| row.author.clone()).collect();
+        author_handler::link_authors_by_name(&mut tx, &book_ids, &names).await?;
+        if self.report.dry_run {
+            tx.rollback().await?;
+            return Ok(inserted.into_iter().filter_map(|row| row.isbn).collect());
+        }
+        // Setiap baris chunk yang tidak tersimpan ditolak sebagai duplikat ISBN di `flush`.
+        let imported = self.report.imported + inserted.len();
+        if let Some(ctx) = summary
+            && imported > 0
+        {
+            let failed = self.report.failed + rows.len() - inserted.len();
+            outbox::enqueue(&mut tx, &ctx.envelope(Self::summary_event(imported, failed))).await?;
+        }
+        tx.commit().await?;
+        self.summary_enqueued = summary.is_some() && imported > 0;
+        Ok(inserted.into_iter().filter_map(|row| row.isbn).collect())
+    }
+
+    // Chunk terakhir membawa event ringkasan. Jika tidak ada chunk tersisa, atau chunk itu
+    // gagal padahal chunk sebelumnya sudah di-commit, event dicatat dalam transaksi sendiri;
+    // kegagalannya dilaporkan di `eventError` karena buku yang tersimpan tidak bisa dibatalkan.
+    async fn finish(mut self, ctx: &EventContext) -> ImportReport {
+        self.flush(Some(ctx)).await;
+        if !self.report.dry_run && self.report.imported > 0 && !self.summary_enqueued {
+            let event = Self::summary_event(self.report.imported, self.report.failed);
+            if let Err(e) = self.enqueue_summary(ctx, event).await {
+                tracing::error!("Gagal mencatat event import buku: {:?}", e);
+                self.report.event_error = Some("Buku tersimpan, tetapi event BooksImported gagal dicatat".to_string());
+            }
+        }
+        self.report
+    }
+
+    fn summary_event(imported: usize, failed: usize) -> ws::WsEvent {
+        ws::WsEvent::BooksImported(BookImportSummary { import_id: Uuid::new_v4(), imported, failed })
+    }
+
+    async fn enqueue_summary(&self, ctx: &EventContext, event: ws::WsEvent) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        outbox::enqueue(&mut tx, &ctx.envelope(event)).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+// Export seluruh buku yang tidak di tempat sampah. Baris dibaca sebagai stream dari
+// database dan dikirim per potongan; channel berkapasitas kecil menahan query bila
+// client membaca lebih lambat.
+#[axum::debug_handler]
+pub async fn export_books(State(state): State<Arc<AppState>>, ValidatedQuery(query): ValidatedQuery<ExportQuery>, _claims: Extension<Claims>) -> Response {
+    let format = query.format;
+    let (tx, rx) = mpsc::channel(4);
+    tokio::spawn(write_export(state.db_pool.clone(), format, tx));
+    let body = Body::from_stream(stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|chunk| (chunk, rx)) }));
+    let disposition = format!("attachment; filename=\"books.{}\"", format.extension());
+    ([(CONTENT_TYPE, format.content_type().to_string()), (CONTENT_DISPOSITION, disposition)], body).into_response()
+}
+
+async fn write_export(pool: PgPool, format: BulkFormat, tx: mpsc::Sender<Result<Bytes, std::io::Error>>) {
+    let mut encoder = ExportEncoder::new(format);
+    let mut buf = encoder.start().to_vec();
+    let mut books = sqlx::query_as!(
+        Book,
+        "SELECT id, title, author, publication_year, isbn, created_at, updated_at, version, created_by, deleted_at FROM books
+         WHERE deleted_at IS NULL
+         ORDER BY created_at, id"
+    )
+    .fetch(&pool);
+    while let Some(book) = books.next().await {
+        // Error di tengah stream memutus respons agar client tidak menerima file terpotong sebagai file utuh.
+        let written = book.map_err(anyhow::Error::from).and_then(|book| encoder.write(&mut buf, &book));
+        if let Err(e) = written {
+            tracing::error!("Export buku gagal: {:?}", e);
+            let _ = tx.send(Err(std::io::Error::other(e))).await;
+            return;
+        }
+        if buf.len() >= EXPORT_FLUSH_BYTES && tx.send(Ok(Bytes::from(std::mem::take(&mut buf)))).await.is_err() {
+            // Client sudah memutus koneksi.
+            return;
+        }
+    }
+    buf.extend_from_slice(encoder.end());
+    let _ = tx.send(Ok(Bytes::from(buf))).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::bulk::MAX_REPORTED_ERRORS;
+    use sqlx::postgres::PgPoolOptions;
+    use std::time::Duration;
+
+    fn book(title: &str, isbn: Option<&str>) -> Result<CreateBook, String> {
+        Ok(CreateBook { title: title.to_string(), author: "Penulis Uji".to_string(), publication_year: Some(2001), isbn: isbn.map(str::to_string) })
+    }
+
+    // ISBN-13 acak dengan checksum yang benar, agar tidak bentrok dengan data yang ada.
+    fn random_isbn() -> String {
+        let body = format!("979{:09}", Uuid::new_v4().as_u128() % 1_000_000_000);
+        let sum: u32 = body.chars().enumerate().map(|(i, c)| c.to_digit(10).unwrap() * if i % 2 == 0 { 1 } else { 3 }).sum();
+        format!("{}{}", body, (10 - sum % 10) % 10)
+    }
+
+    // Test yang butuh database dilewati jika `DATABASE_URL` tidak diatur.
+    async fn pool() -> Option<PgPool> {
+        let url = std::env::var("DATABASE_URL").ok()?;
+        Some(PgPool::connect(&url).await.expect("gagal terhubung ke DATABASE_URL"))
+    }
+
+    #[test]
+    fn csv_header_is_trimmed_and_accepts_export_names() {
+        let mut parser = RowParser::new(BulkFormat::Csv);
+        assert!(parser.parse(b" title , author ,publicationYear,isbn").is_none());
+        let row = parser.parse(b"\"Dune, Part 1\",Frank Herbert,1965,").unwrap().unwrap();
+        assert_eq!((row.title.as_str(), row.author.as_str(), row.publication_year, row.isbn), ("Dune, Part 1", "Frank Herbert", Some(1965), None));
+        let row = parser.parse(b"Emma,Jane Austen,,9780306406157").unwrap().unwrap();
+        assert_eq!((row.publication_year, row.isbn.as_deref()), (None, Some("9780306406157")));
+        assert!(parser.parse(b"Tanpa Penulis").unwrap().is_err());
+        assert!(parser.parse(b"Judul,Penulis,bukan-angka,").unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn reports_invalid_rows_with_their_numbers() {
+        let pool = PgPoolOptions::new().connect_lazy("postgres://127.0.0.1/tidak_dipakai").unwrap();
+        let mut importer = Importer::new(&pool, Uuid::new_v4(), true, 10);
+        importer.add(book("", None)).await;
+        importer.add(Err("JSON tidak valid".to_string())).await;
+        importer.add(book("Judul", Some("123"))).await;
+        let report = importer.report;
+        assert_eq!((report.total, report.failed), (3, 3));
+        assert_eq!(report.errors.iter().map(|e| e.row).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(report.errors[1].message, "JSON tidak valid");
+    }
+
+    #[test]
+    fn error_report_is_truncated() {
+        let mut report = ImportReport::default();
+        for row in 1..=MAX_REPORTED_ERRORS + 5 {
+            report.reject(row, "gagal".to_string());
+        }
+        assert_eq!(report.failed, MAX_REPORTED_ERRORS + 5);
+        assert_eq!(report.errors.len(), MAX_REPORTED_ERRORS);
+        assert!(report.errors_truncated);
+    }
+
+    #[tokio::test]
+    async fn failed_flush_aborts_and_keeps_the_report() {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(200))
+            .connect_lazy("postgres://postgres@127.0.0.1:1/tidak_ada")
+            .unwrap();
+        let mut importer = Importer::new(&pool, Uuid::new_v4(), false, 2);
+        importer.add(book("A", None)).await;
+        importer.add(book("B", None)).await;
+        importer.add(book("C", None)).await;
+        let report = importer.finish(&EventContext::default()).await;
+        assert!(report.aborted.is_some());
+        assert_eq!((report.total, report.imported, report.failed), (2, 0, 2));
+    }
+
+    #[tokio::test]
+    async fn dry_run_rolls_back_and_rejects_duplicate_isbns_in_a_chunk() {
+        let Some(pool) = pool().await else { return };
+        let isbn = random_isbn();
+        let other = random_isbn();
+        let mut importer = Importer::new(&pool, Uuid::new_v4(), true, 10);
+        importer.add(book("Pertama", Some(&isbn))).await;
+        importer.add(book("Tanpa ISBN", None)).await;
+        importer.add(book("Duplikat", Some(&isbn))).await;
+        importer.add(book("Lain", Some(&other))).await;
+        let report = importer.finish(&EventContext::default()).await;
+        assert_eq!((report.total, report.imported, report.failed), (4, 3, 1));
+        assert_eq!(report.errors[0].row, 3);
+        assert!(report.aborted.is_none());
+
+        let stored = sqlx::query_scalar!("SELECT COUNT(*) FROM books WHERE isbn = ANY($1)", &[isbn, other] as &[String])
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(stored, Some(0));
+    }
+
+    // Data uji di-commit, jadi dihapus lagi di akhir.
+    #[tokio::test]
+    async fn summary_event_is_committed_with_the_last_chunk() {
+        let Some(pool) = pool().await else { return };
+        let isbns = [random_isbn(), random_isbn(), random_isbn()];
+        let ctx = EventContext::for_user(&format!("uji-import-{}", Uuid::new_v4()));
+        let mut importer = Importer::new(&pool, Uuid::new_v4(), false, 2);
+        for isbn in &isbns {
+            importer.add(book("Buku Import", Some(isbn))).await;
+        }
+        let report = importer.finish(&ctx).await;
+        assert_eq!((report.imported, report.failed), (3, 0));
+        assert!(report.event_error.is_none());
+
+        let events = sqlx::query!(
+            r#"SELECT o.payload->'data'->>'imported' AS imported,
+                      (o.xact_id::text::bigint % 4294967296) = (SELECT b.xmin::text::bigint FROM books b WHERE b.isbn = $2) AS "same_xact!"
+               FROM event_outbox o WHERE o.payload->>'actor' = $1"#,
+            ctx.actor,
+            isbns[2]
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        sqlx::query!("DELETE FROM event_outbox WHERE payload->>'actor' = $1", ctx.actor)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query!("DELETE FROM books WHERE isbn = ANY($1)", &isbns)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].imported.as_deref(), Some("3"));
+        assert!(events[0].same_xact);
+    }
+}
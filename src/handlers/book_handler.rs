@@ -1,73 +1,302 @@
-use crate::{auth::Claims, error::AppError, models::book::{Book, CreateBook, UpdateBook}, ws, AppState};
-use axum::{extract::{Path, State}, Extension, Json};
+use crate::{auth::Claims, error::AppError, etag::{self, Preconditions}, extract::{MergePatch, ValidatedQuery}, handlers::author_handler, models::{author::BookAuthors, isbn, book::{Book, BookCursor, BookPatch, BookQuery, CreateBook, SortValue, UpdateBook}, pagination::{Cursor, Page, PageQuery}}, outbox, ws::{self, envelope::EventContext}, AppState};
+use axum::{extract::{Path, Query, State}, response::Response, Extension, Json};
+use sqlx::{Postgres, QueryBuilder};
 use std::sync::Arc;
 use uuid::Uuid;
 use validator::Validate;
 
 #[axum::debug_handler]
-pub async fn get_all_books(State(state): State<Arc<AppState>>, _claims: Extension<Claims>) -> Result<Json<Vec<Book>>, AppError> {
-    let books = sqlx::query_as!(Book, "SELECT * FROM books ORDER BY created_at DESC")
-        .fetch_all(&state.db_pool)
-        .await?;
-    Ok(Json(books))
+pub async fn get_all_books(State(state): State<Arc<AppState>>, ValidatedQuery(query): ValidatedQuery<BookQuery>, _claims: Extension<Claims>) -> Result<Json<Page<Book>>, AppError> {
+    let limit = query.limit();
+    let cursor = query.cursor()?;
+
+    // Semua nilai dari client dikirim sebagai bind parameter; hanya nama kolom dan arah
+    // urutan dari enum yang ditulis langsung ke SQL.
+    let mut builder = QueryBuilder::<Postgres>::new("SELECT id, title, author, publication_year, isbn, created_at, updated_at, version, created_by, deleted_at FROM books WHERE deleted_at IS NULL");
+    if let Some(author) = &query.author {
+        builder.push(" AND lower(author) = lower(").push_bind(author).push(")");
+    }
+    if let Some(title) = &query.title {
+        builder.push(" AND title ILIKE ").push_bind(format!("%{}%", escape_like(title)));
+    }
+    if let Some(year_from) = query.year_from {
+        builder.push(" AND publication_year >= ").push_bind(year_from);
+    }
+    if let Some(year_to) = query.year_to {
+        builder.push(" AND publication_year <= ").push_bind(year_to);
+    }
+    if let Some(created_after) = query.created_after {
+        builder.push(" AND created_at >= ").push_bind(created_after);
+    }
+    if let Some(created_before) = query.created_before {
+        builder.push(" AND created_at < ").push_bind(created_before);
+    }
+    if let Some(updated_after) = query.updated_after {
+        builder.push(" AND updated_at >= ").push_bind(updated_after);
+    }
+    if let Some(updated_before) = query.updated_before {
+        builder.push(" AND updated_at < ").push_bind(updated_before);
+    }
+
+    let (sort, order) = (query.sort.sql(), query.order);
+    if let Some(cursor) = cursor {
+        builder.push(format_args!(" AND ({}, id) {} (", sort, order.after()));
+        match cursor.value {
+            SortValue::Text(value) => builder.push_bind(value),
+            SortValue::Int(value) => builder.push_bind(value),
+            SortValue::Time(value) => builder.push_bind(value),
+        };
+        builder.push(", ").push_bind(cursor.id).push(")");
+    }
+    builder.push(format_args!(" ORDER BY {} {dir}, id {dir} LIMIT ", sort, dir = order.sql()));
+    builder.push_bind(limit + 1);
+
+    let books = builder.build_query_as::<Book>().fetch_all(&state.db_pool).await?;
+    Ok(Json(Page::new(books, limit, |book| BookCursor::new(query.sort, order, book).encode())))
+}
+
+// Meloloskan karakter wildcard LIKE agar input dicari apa adanya.
+pub(crate) fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
 }
 
 #[axum::debug_handler]
-pub async fn create_book(State(state): State<Arc<AppState>>, _claims: Extension<Claims>, Json(payload): Json<CreateBook>) -> Result<Json<Book>, AppError> {
+pub async fn create_book(State(state): State<Arc<AppState>>, Extension(claims): Extension<Claims>, ctx: EventContext, Json(payload): Json<CreateBook>) -> Result<Response, AppError> {
     payload.validate()?;
+    let created_by = claims.user_id().ok_or(AppError::InvalidToken)?;
+    let mut tx = state.db_pool.begin().await?;
     let book = sqlx::query_as!(
         Book,
-        "INSERT INTO books (title, author, publication_year) VALUES ($1, $2, $3) RETURNING *",
+        "INSERT INTO books (title, author, publication_year, isbn, created_by) VALUES ($1, $2, $3, $4, $5) RETURNING id, title, author, publication_year, isbn, created_at, updated_at, version, created_by, deleted_at",
         payload.title,
         payload.author,
-        payload.publication_year
+        payload.publication_year,
+        payload.isbn.as_deref().and_then(isbn::normalize),
+        created_by
     )
-    .fetch_one(&state.db_pool)
-    .await?;
-    ws::broadcast_event(ws::WsEvent::BookCreated(book.clone()));
-    Ok(Json(book))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(isbn_conflict)?;
+    author_handler::link_authors_by_name(&mut tx, &[book.id], std::slice::from_ref(&book.author)).await?;
+    outbox::enqueue(&mut tx, &ctx.envelope(ws::WsEvent::BookCreated(book.clone()))).await?;
+    tx.commit().await?;
+    state.outbox.wake();
+    Ok(etag::with_etag(book.version, book))
 }
 
 #[axum::debug_handler]
-pub async fn get_book_by_id(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, _claims: Extension<Claims>) -> Result<Json<Book>, AppError> {
-    let book = sqlx::query_as!(Book, "SELECT * FROM books WHERE id = $1", id)
+pub async fn get_book_by_id(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, preconditions: Preconditions, _claims: Extension<Claims>) -> Result<Response, AppError> {
+    let book = sqlx::query_as!(Book, "SELECT id, title, author, publication_year, isbn, created_at, updated_at, version, created_by, deleted_at FROM books WHERE id = $1 AND deleted_at IS NULL", id)
         .fetch_optional(&state.db_pool)
         .await?
         .ok_or_else(|| AppError::NotFound("Buku".to_string()))?;
-    Ok(Json(book))
+    if let Some(not_modified) = preconditions.not_modified(book.version) {
+        return Ok(not_modified);
+    }
+    Ok(etag::with_etag(book.version, book))
 }
 
 #[axum::debug_handler]
-pub async fn update_book(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, _claims: Extension<Claims>, Json(payload): Json<UpdateBook>) -> Result<Json<Book>, AppError> {
-    payload.validate()?;
-    let book = sqlx::query_as!(Book, "SELECT * FROM books WHERE id = $1", id)
+pub async fn get_book_by_isbn(State(state): State<Arc<AppState>>, Path(input): Path<String>, preconditions: Preconditions, _claims: Extension<Claims>) -> Result<Response, AppError> {
+    let isbn = isbn::normalize(&input).ok_or_else(|| AppError::BadRequest("ISBN-10 atau ISBN-13 tidak valid".to_string()))?;
+    let book = sqlx::query_as!(Book, "SELECT id, title, author, publication_year, isbn, created_at, updated_at, version, created_by, deleted_at FROM books WHERE isbn = $1 AND deleted_at IS NULL", isbn)
         .fetch_optional(&state.db_pool)
         .await?
         .ok_or_else(|| AppError::NotFound("Buku".to_string()))?;
+    if let Some(not_modified) = preconditions.not_modified(book.version) {
+        return Ok(not_modified);
+    }
+    Ok(etag::with_etag(book.version, book))
+}
+
+#[axum::debug_handler]
+pub async fn update_book(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>, preconditions: Preconditions, ctx: EventContext, Json(payload): Json<UpdateBook>) -> Result<Response, AppError> {
+    payload.validate()?;
+    let book = apply_book_patch(&state, id, &claims, &preconditions, &ctx, payload.into()).await?;
+    Ok(etag::with_etag(book.version, book))
+}
 
-    let title = payload.title.unwrap_or(book.title);
-    let author = payload.author.unwrap_or(book.author);
-    let pub_year = payload.publication_year.or(book.publication_year);
+#[axum::debug_handler]
+pub async fn patch_book(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>, preconditions: Preconditions, ctx: EventContext, MergePatch(patch): MergePatch<BookPatch>) -> Result<Response, AppError> {
+    let book = apply_book_patch(&state, id, &claims, &preconditions, &ctx, patch).await?;
+    Ok(etag::with_etag(book.version, book))
+}
+
+// Menerapkan patch dalam satu UPDATE. CTE `old` mengunci baris dan menyimpan nilai
+// sebelum perubahan untuk diff pada event `BookUpdated`. Baris hanya dikunci jika pemanggil
+// pemilik buku atau admin dan, bila ada `If-Match`, versinya cocok.
+async fn apply_book_patch(state: &AppState, id: Uuid, claims: &Claims, preconditions: &Preconditions, ctx: &EventContext, patch: BookPatch) -> Result<Book, AppError> {
+    // `null` untuk judul dan penulis sudah ditolak validasi, jadi `None` berarti tidak diubah.
+    let title = patch.title.into_change().flatten();
+    let author = patch.author.into_change().flatten();
+    let publication_year = patch.publication_year.into_change();
+    // Sudah divalidasi, jadi `normalize` selalu berhasil untuk nilai yang dikirim.
+    let isbn = patch.isbn.into_change().map(|isbn| isbn.as_deref().and_then(isbn::normalize));
 
-    let updated_book = sqlx::query_as!(
+    let expected = preconditions.expected_versions();
+    let mut tx = state.db_pool.begin().await?;
+    let row = sqlx::query!(
+        r#"WITH old AS (
+               SELECT id, title, author, publication_year, isbn, updated_at, version FROM books
+               WHERE id = $5 AND deleted_at IS NULL AND ($6::bigint[] IS NULL OR version = ANY($6))
+                 AND ($7 OR created_by = $8)
+               FOR UPDATE
+           )
+           UPDATE books b
+           SET title = COALESCE($1, b.title),
+               author = COALESCE($2, b.author),
+               publication_year = CASE WHEN $3 THEN $4 ELSE b.publication_year END,
+               isbn = CASE WHEN $9 THEN $10 ELSE b.isbn END,
+               updated_at = NOW(),
+               version = b.version + 1
+           FROM old
+           WHERE b.id = old.id
+           RETURNING b.id, b.title, b.author, b.publication_year, b.isbn, b.created_at, b.updated_at, b.version, b.created_by, b.deleted_at,
+                     old.title AS old_title, old.author AS old_author,
+                     old.publication_year AS old_publication_year, old.isbn AS old_isbn, old.updated_at AS old_updated_at,
+                     old.version AS old_version"#,
+        title,
+        author,
+        publication_year.is_some(),
+        publication_year.flatten(),
+        id,
+        expected.as_deref(),
+        claims.is_admin(),
+        claims.user_id(),
+        isbn.is_some(),
+        isbn.flatten()
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(isbn_conflict)?;
+    let Some(row) = row else {
+        return Err(book_miss(&mut tx, id, claims).await?);
+    };
+
+    let book = Book {
+        id: row.id,
+        title: row.title,
+        author: row.author,
+        publication_year: row.publication_year,
+        isbn: row.isbn,
+        created_at: row.created_at,
+        updated_at: row.updated_at,
+        version: row.version,
+        created_by: row.created_by,
+        deleted_at: row.deleted_at,
+    };
+    let old = Book {
+        title: row.old_title,
+        author: row.old_author,
+        publication_year: row.old_publication_year,
+        isbn: row.old_isbn,
+        updated_at: row.old_updated_at,
+        version: row.old_version,
+        ..book.clone()
+    };
+    let changes = ws::diff::diff(&old, &book);
+    outbox::enqueue(&mut tx, &ctx.envelope(ws::WsEvent::BookUpdated(book.clone())).with_changes(changes)).await?;
+    // `author` tetap teks tampilan, tetapi relasi penulis utama diikutkan agar tidak tertinggal.
+    if book.author != old.author
+        && let Some(authors) = author_handler::relink_primary_author(&mut tx, book.id, &book.author).await?
+    {
+        let event = ws::WsEvent::BookAuthorsUpdated(BookAuthors { book_id: book.id, authors });
+        outbox::enqueue(&mut tx, &ctx.envelope(event)).await?;
+    }
+    tx.commit().await?;
+    state.outbox.wake();
+    Ok(book)
+}
+
+// Memindahkan buku ke tempat sampah; baris dihapus permanen oleh purge setelah masa retensi.
+#[axum::debug_handler]
+pub async fn delete_book(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>, preconditions: Preconditions, ctx: EventContext) -> Result<(), AppError> {
+    let expected = preconditions.expected_versions();
+    let mut tx = state.db_pool.begin().await?;
+    let result = sqlx::query!(
+        "UPDATE books SET deleted_at = NOW(), version = version + 1
+         WHERE id = $1 AND deleted_at IS NULL AND ($2::bigint[] IS NULL OR version = ANY($2))
+           AND ($3 OR created_by = $4)",
+        id,
+        expected.as_deref(),
+        claims.is_admin(),
+        claims.user_id()
+    )
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(book_miss(&mut tx, id, &claims).await?);
+    }
+    outbox::enqueue(&mut tx, &ctx.envelope(ws::WsEvent::BookDeleted(id))).await?;
+    tx.commit().await?;
+    state.outbox.wake();
+    Ok(())
+}
+
+#[axum::debug_handler]
+pub async fn get_trashed_books(State(state): State<Arc<AppState>>, Query(page): Query<PageQuery>, _claims: Extension<Claims>) -> Result<Json<Page<Book>>, AppError> {
+    let limit = page.limit();
+    let (cursor_at, cursor_id) = page.cursor()?.map(|c| (c.at, c.id)).unzip();
+    let books = sqlx::query_as!(
         Book,
-        "UPDATE books SET title = $1, author = $2, publication_year = $3, updated_at = NOW() WHERE id = $4 RETURNING *",
-        title, author, pub_year, id
+        "SELECT id, title, author, publication_year, isbn, created_at, updated_at, version, created_by, deleted_at FROM books
+         WHERE deleted_at IS NOT NULL AND ($1::timestamptz IS NULL OR (deleted_at, id) < ($1, $2::uuid))
+         ORDER BY deleted_at DESC, id DESC
+         LIMIT $3",
+        cursor_at,
+        cursor_id,
+        limit + 1
     )
-    .fetch_one(&state.db_pool)
+    .fetch_all(&state.db_pool)
     .await?;
-    ws::broadcast_event(ws::WsEvent::BookUpdated(updated_book.clone()));
-    Ok(Json(updated_book))
+    Ok(Json(Page::new(books, limit, |book| Cursor::new(book.deleted_at.unwrap_or_default(), book.id).encode())))
 }
 
 #[axum::debug_handler]
-pub async fn delete_book(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, _claims: Extension<Claims>) -> Result<(), AppError> {
-    let result = sqlx::query!("DELETE FROM books WHERE id = $1", id)
-        .execute(&state.db_pool)
+pub async fn restore_book(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>, preconditions: Preconditions, ctx: EventContext) -> Result<Response, AppError> {
+    let mut tx = state.db_pool.begin().await?;
+    let trashed = sqlx::query!("SELECT created_by, version FROM books WHERE id = $1 AND deleted_at IS NOT NULL FOR UPDATE", id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Buku di tempat sampah".to_string()))?;
+    if !claims.can_modify(trashed.created_by) {
+        return Err(AppError::Forbidden);
+    }
+    if preconditions.expected_versions().is_some_and(|expected| !expected.contains(&trashed.version)) {
+        return Err(etag::missed(true, "Buku"));
+    }
+    let book = sqlx::query_as!(
+        Book,
+        "UPDATE books SET deleted_at = NULL, updated_at = NOW(), version = version + 1
+         WHERE id = $1 AND deleted_at IS NOT NULL
+         RETURNING id, title, author, publication_year, isbn, created_at, updated_at, version, created_by, deleted_at",
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    outbox::enqueue(&mut tx, &ctx.envelope(ws::WsEvent::BookRestored(book.clone()))).await?;
+    tx.commit().await?;
+    state.outbox.wake();
+    Ok(etag::with_etag(book.version, book))
+}
+
+// Alasan UPDATE bersyarat tidak mengenai baris: buku tidak ada (404), pemanggil bukan
+// pemilik atau admin (403), atau versinya tidak cocok dengan `If-Match` (412).
+async fn book_miss(tx: &mut sqlx::Transaction<'_, Postgres>, id: Uuid, claims: &Claims) -> Result<AppError, sqlx::Error> {
+    let owner = sqlx::query_scalar!("SELECT created_by FROM books WHERE id = $1 AND deleted_at IS NULL", id)
+        .fetch_optional(&mut **tx)
         .await?;
-    if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Buku".to_string()));
+    Ok(match owner {
+        Some(owner) if !claims.can_modify(owner) => AppError::Forbidden,
+        owner => etag::missed(owner.is_some(), "Buku"),
+    })
+}
+
+fn isbn_conflict(e: sqlx::Error) -> AppError {
+    if let sqlx::Error::Database(db_err) = &e
+        && db_err.constraint() == Some("books_isbn_key")
+    {
+        return AppError::Conflict("ISBN sudah dipakai buku lain (termasuk yang ada di tempat sampah)".to_string());
     }
-    ws::broadcast_event(ws::WsEvent::BookDeleted(id));
-    Ok(())
-}
\ No newline at end of file
+    AppError::DatabaseError(e)
+}
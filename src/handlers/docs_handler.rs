@@ -0,0 +1,9 @@
+use crate::ws;
+use axum::Json;
+use serde_json::Value;
+
+// Deskripsi AsyncAPI protokol WebSocket, tidak memerlukan autentikasi.
+#[axum::debug_handler]
+pub async fn get_asyncapi() -> Json<&'static Value> {
+    Json(ws::asyncapi::document())
+}
@@ -1,75 +1,241 @@
-use crate::{auth::Claims, error::AppError, models::email::{CreateEmail, Email, UpdateEmail}, ws, AppState};
-use axum::{extract::{Path, State}, Extension, Json}; // Added Extension
+use crate::{auth::Claims, error::AppError, etag::{self, Preconditions}, extract::MergePatch, models::{email::{CreateEmail, Email, EmailPatch, UpdateEmail}, pagination::{Cursor, Page, PageQuery}}, outbox, ws::{self, envelope::EventContext}, AppState};
+use axum::{extract::{Path, Query, State}, response::Response, Extension, Json}; // Added Extension
+use sqlx::Postgres;
 use std::sync::Arc;
 use uuid::Uuid;
 use validator::Validate;
 
 #[axum::debug_handler]
-pub async fn get_all_emails(State(state): State<Arc<AppState>>, _claims: Extension<Claims>) -> Result<Json<Vec<Email>>, AppError> {
-    let emails = sqlx::query_as!(Email, "SELECT * FROM emails ORDER BY sent_at DESC")
-        .fetch_all(&state.db_pool)
-        .await?;
-    Ok(Json(emails))
+pub async fn get_all_emails(State(state): State<Arc<AppState>>, Query(page): Query<PageQuery>, _claims: Extension<Claims>) -> Result<Json<Page<Email>>, AppError> {
+    let limit = page.limit();
+    let (cursor_at, cursor_id) = page.cursor()?.map(|c| (c.at, c.id)).unzip();
+    let emails = sqlx::query_as!(
+        Email,
+        "SELECT id, sender, recipient, subject, body, sent_at, version, deleted_at FROM emails
+         WHERE deleted_at IS NULL AND ($1::timestamptz IS NULL OR (sent_at, id) < ($1, $2::uuid))
+         ORDER BY sent_at DESC, id DESC
+         LIMIT $3",
+        cursor_at,
+        cursor_id,
+        limit + 1
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+    Ok(Json(Page::new(emails, limit, |email| Cursor::new(email.sent_at, email.id).encode())))
 }
 
 #[axum::debug_handler]
-pub async fn create_email(State(state): State<Arc<AppState>>, _claims: Extension<Claims>, Json(payload): Json<CreateEmail>) -> Result<Json<Email>, AppError> {
+pub async fn create_email(State(state): State<Arc<AppState>>, ctx: EventContext, Json(payload): Json<CreateEmail>) -> Result<Response, AppError> {
     payload.validate()?;
+    let mut tx = state.db_pool.begin().await?;
     let email = sqlx::query_as!(
         Email,
-        "INSERT INTO emails (sender, recipient, subject, body) VALUES ($1, $2, $3, $4) RETURNING *",
+        "INSERT INTO emails (sender, recipient, subject, body) VALUES ($1, $2, $3, $4) RETURNING id, sender, recipient, subject, body, sent_at, version, deleted_at",
         payload.sender,
         payload.recipient,
         payload.subject,
         payload.body
     )
-    .fetch_one(&state.db_pool)
+    .fetch_one(&mut *tx)
     .await?;
-    ws::broadcast_event(ws::WsEvent::EmailCreated(email.clone()));
-    Ok(Json(email))
+    outbox::enqueue(&mut tx, &ctx.envelope(ws::WsEvent::EmailCreated(email.clone()))).await?;
+    tx.commit().await?;
+    state.outbox.wake();
+    Ok(etag::with_etag(email.version, email))
 }
 
 #[axum::debug_handler]
-pub async fn get_email_by_id(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, _claims: Extension<Claims>) -> Result<Json<Email>, AppError> {
-    let email = sqlx::query_as!(Email, "SELECT * FROM emails WHERE id = $1", id)
+pub async fn get_email_by_id(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, preconditions: Preconditions, _claims: Extension<Claims>) -> Result<Response, AppError> {
+    let email = sqlx::query_as!(Email, "SELECT id, sender, recipient, subject, body, sent_at, version, deleted_at FROM emails WHERE id = $1 AND deleted_at IS NULL", id)
         .fetch_optional(&state.db_pool)
         .await?
         .ok_or_else(|| AppError::NotFound("Email".to_string()))?;
-    Ok(Json(email))
+    if let Some(not_modified) = preconditions.not_modified(email.version) {
+        return Ok(not_modified);
+    }
+    Ok(etag::with_etag(email.version, email))
 }
 
 #[axum::debug_handler]
-pub async fn update_email(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, _claims: Extension<Claims>, Json(payload): Json<UpdateEmail>) -> Result<Json<Email>, AppError> {
+pub async fn update_email(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, preconditions: Preconditions, ctx: EventContext, Json(payload): Json<UpdateEmail>) -> Result<Response, AppError> {
     payload.validate()?;
-    let email = sqlx::query_as!(Email, "SELECT * FROM emails WHERE id = $1", id)
-        .fetch_optional(&state.db_pool)
-        .await?
-        .ok_or_else(|| AppError::NotFound("Email".to_string()))?;
+    let email = apply_email_patch(&state, id, &preconditions, &ctx, payload.into()).await?;
+    Ok(etag::with_etag(email.version, email))
+}
 
-    let subject = payload.subject.unwrap_or(email.subject);
-    let body = payload.body.or(email.body);
+#[axum::debug_handler]
+pub async fn patch_email(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, preconditions: Preconditions, ctx: EventContext, MergePatch(patch): MergePatch<EmailPatch>) -> Result<Response, AppError> {
+    let email = apply_email_patch(&state, id, &preconditions, &ctx, patch).await?;
+    Ok(etag::with_etag(email.version, email))
+}
 
-    let updated_email = sqlx::query_as!(
-        Email,
-        "UPDATE emails SET subject = $1, body = $2 WHERE id = $3 RETURNING *",
+// Menerapkan patch dalam satu UPDATE. CTE `old` mengunci baris dan menyimpan nilai
+// sebelum perubahan untuk diff pada event `EmailUpdated`. Jika ada `If-Match`, baris hanya
+// dikunci ketika versinya cocok.
+async fn apply_email_patch(state: &AppState, id: Uuid, preconditions: &Preconditions, ctx: &EventContext, patch: EmailPatch) -> Result<Email, AppError> {
+    // `null` untuk subjek sudah ditolak validasi, jadi `None` berarti tidak diubah.
+    let subject = patch.subject.into_change().flatten();
+    let body = patch.body.into_change();
+
+    let expected = preconditions.expected_versions();
+    let mut tx = state.db_pool.begin().await?;
+    let row = sqlx::query!(
+        r#"WITH old AS (
+               SELECT id, subject, body, version FROM emails
+               WHERE id = $4 AND deleted_at IS NULL AND ($5::bigint[] IS NULL OR version = ANY($5))
+               FOR UPDATE
+           )
+           UPDATE emails e
+           SET subject = COALESCE($1, e.subject),
+               body = CASE WHEN $2 THEN $3 ELSE e.body END,
+               version = e.version + 1
+           FROM old
+           WHERE e.id = old.id
+           RETURNING e.id, e.sender, e.recipient, e.subject, e.body, e.sent_at, e.version, e.deleted_at,
+                     old.subject AS old_subject, old.body AS old_body, old.version AS old_version"#,
         subject,
-        body,
-        id
+        body.is_some(),
+        body.flatten(),
+        id,
+        expected.as_deref()
     )
-    .fetch_one(&state.db_pool)
+    .fetch_optional(&mut *tx)
     .await?;
-    ws::broadcast_event(ws::WsEvent::EmailUpdated(updated_email.clone()));
-    Ok(Json(updated_email))
+    let Some(row) = row else {
+        return Err(etag::missed(email_exists(&mut tx, id).await?, "Email"));
+    };
+
+    let email = Email {
+        id: row.id,
+        sender: row.sender,
+        recipient: row.recipient,
+        subject: row.subject,
+        body: row.body,
+        sent_at: row.sent_at,
+        version: row.version,
+        deleted_at: row.deleted_at,
+    };
+    let old = Email { subject: row.old_subject, body: row.old_body, version: row.old_version, ..email.clone() };
+    let changes = ws::diff::diff(&old, &email);
+    outbox::enqueue(&mut tx, &ctx.envelope(ws::WsEvent::EmailUpdated(email.clone())).with_changes(changes)).await?;
+    tx.commit().await?;
+    state.outbox.wake();
+    Ok(email)
 }
 
+// Memindahkan email ke tempat sampah; baris dihapus permanen oleh purge setelah masa retensi.
+// Aturan aksesnya sama dengan pemulihan: hanya admin atau pengirim/penerima email.
 #[axum::debug_handler]
-pub async fn delete_email(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, _claims: Extension<Claims>) -> Result<(), AppError> {
-    let result = sqlx::query!("DELETE FROM emails WHERE id = $1", id)
-        .execute(&state.db_pool)
-        .await?;
+pub async fn delete_email(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>, preconditions: Preconditions, ctx: EventContext) -> Result<(), AppError> {
+    let expected = preconditions.expected_versions();
+    let mut tx = state.db_pool.begin().await?;
+    let result = sqlx::query!(
+        "UPDATE emails e SET deleted_at = NOW(), version = e.version + 1
+         WHERE e.id = $1 AND e.deleted_at IS NULL AND ($2::bigint[] IS NULL OR e.version = ANY($2))
+           AND ($3 OR EXISTS (SELECT 1 FROM users u
+                              WHERE u.id = $4 AND lower(u.email) IN (lower(e.sender), lower(e.recipient))))",
+        id,
+        expected.as_deref(),
+        claims.is_admin(),
+        claims.user_id()
+    )
+    .execute(&mut *tx)
+    .await?;
     if result.rows_affected() == 0 {
-        return Err(AppError::NotFound("Email".to_string()));
+        return Err(email_miss(&mut tx, id, &claims).await?);
     }
-    ws::broadcast_event(ws::WsEvent::EmailDeleted(id));
+    outbox::enqueue(&mut tx, &ctx.envelope(ws::WsEvent::EmailDeleted(id))).await?;
+    tx.commit().await?;
+    state.outbox.wake();
     Ok(())
-}
\ No newline at end of file
+}
+
+// Admin melihat seluruh tempat sampah; pengguna lain hanya email yang ia kirim atau terima.
+#[axum::debug_handler]
+pub async fn get_trashed_emails(State(state): State<Arc<AppState>>, Query(page): Query<PageQuery>, Extension(claims): Extension<Claims>) -> Result<Json<Page<Email>>, AppError> {
+    let limit = page.limit();
+    let (cursor_at, cursor_id) = page.cursor()?.map(|c| (c.at, c.id)).unzip();
+    let emails = sqlx::query_as!(
+        Email,
+        "SELECT e.id, e.sender, e.recipient, e.subject, e.body, e.sent_at, e.version, e.deleted_at FROM emails e
+         WHERE e.deleted_at IS NOT NULL AND ($1::timestamptz IS NULL OR (e.deleted_at, e.id) < ($1, $2::uuid))
+           AND ($4 OR EXISTS (SELECT 1 FROM users u
+                              WHERE u.id = $5 AND lower(u.email) IN (lower(e.sender), lower(e.recipient))))
+         ORDER BY e.deleted_at DESC, e.id DESC
+         LIMIT $3",
+        cursor_at,
+        cursor_id,
+        limit + 1,
+        claims.is_admin(),
+        claims.user_id()
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+    Ok(Json(Page::new(emails, limit, |email| Cursor::new(email.deleted_at.unwrap_or_default(), email.id).encode())))
+}
+
+// Hanya admin atau pengirim/penerima email yang boleh memulihkannya.
+#[axum::debug_handler]
+pub async fn restore_email(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>, preconditions: Preconditions, ctx: EventContext) -> Result<Response, AppError> {
+    let mut tx = state.db_pool.begin().await?;
+    let trashed = sqlx::query!(
+        r#"SELECT e.version,
+                  ($2 OR EXISTS (SELECT 1 FROM users u
+                                 WHERE u.id = $3 AND lower(u.email) IN (lower(e.sender), lower(e.recipient)))) AS "allowed!"
+           FROM emails e
+           WHERE e.id = $1 AND e.deleted_at IS NOT NULL
+           FOR UPDATE OF e"#,
+        id,
+        claims.is_admin(),
+        claims.user_id()
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Email di tempat sampah".to_string()))?;
+    if !trashed.allowed {
+        return Err(AppError::Forbidden);
+    }
+    if preconditions.expected_versions().is_some_and(|expected| !expected.contains(&trashed.version)) {
+        return Err(etag::missed(true, "Email"));
+    }
+    let email = sqlx::query_as!(
+        Email,
+        "UPDATE emails SET deleted_at = NULL, version = version + 1
+         WHERE id = $1 AND deleted_at IS NOT NULL
+         RETURNING id, sender, recipient, subject, body, sent_at, version, deleted_at",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Email di tempat sampah".to_string()))?;
+    outbox::enqueue(&mut tx, &ctx.envelope(ws::WsEvent::EmailRestored(email.clone()))).await?;
+    tx.commit().await?;
+    state.outbox.wake();
+    Ok(etag::with_etag(email.version, email))
+}
+
+// Membedakan 403 (email ada tetapi bukan milik pemanggil) dari 412/404 setelah UPDATE gagal.
+async fn email_miss(tx: &mut sqlx::Transaction<'_, Postgres>, id: Uuid, claims: &Claims) -> Result<AppError, sqlx::Error> {
+    let allowed = sqlx::query_scalar!(
+        r#"SELECT ($2 OR EXISTS (SELECT 1 FROM users u
+                                 WHERE u.id = $3 AND lower(u.email) IN (lower(e.sender), lower(e.recipient)))) AS "allowed!"
+           FROM emails e
+           WHERE e.id = $1 AND e.deleted_at IS NULL"#,
+        id,
+        claims.is_admin(),
+        claims.user_id()
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(match allowed {
+        Some(false) => AppError::Forbidden,
+        allowed => etag::missed(allowed.is_some(), "Email"),
+    })
+}
+
+async fn email_exists(tx: &mut sqlx::Transaction<'_, Postgres>, id: Uuid) -> Result<bool, sqlx::Error> {
+    let exists = sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM emails WHERE id = $1 AND deleted_at IS NULL)", id)
+        .fetch_one(&mut **tx)
+        .await?;
+    Ok(exists.unwrap_or(false))
+}
@@ -1,4 +1,11 @@
 pub mod auth_handler;
+pub mod author_handler;
+pub mod book_bulk_handler;
 pub mod book_handler;
+pub mod docs_handler;
 pub mod email_handler;
+pub mod presence_handler;
+pub mod search_handler;
+pub mod sse_handler;
+pub mod webhook_handler;
 pub mod ws_handler;
\ No newline at end of file
@@ -0,0 +1,46 @@
+use crate::{auth::Claims, error::AppError, presence::OnlineUser, ws::LimitPolicy, AppState};
+use axum::{extract::State, Extension, Json};
+use serde::Serialize;
+use std::sync::Arc;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionCounts {
+    pub total: usize,
+    pub max_connections: usize,
+    pub max_connections_per_user: usize,
+    pub limit_policy: LimitPolicy,
+    pub users: Vec<UserConnectionCount>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserConnectionCount {
+    pub user_id: String,
+    pub connections: usize,
+}
+
+// Admin juga melihat detail setiap koneksi, user biasa hanya ringkasannya.
+#[axum::debug_handler]
+pub async fn get_online_users(State(state): State<Arc<AppState>>, Extension(claims): Extension<Claims>) -> Result<Json<Vec<OnlineUser>>, AppError> {
+    Ok(Json(state.presence.list(claims.is_admin())))
+}
+
+#[axum::debug_handler]
+pub async fn get_connection_counts(State(state): State<Arc<AppState>>, Extension(claims): Extension<Claims>) -> Result<Json<ConnectionCounts>, AppError> {
+    claims.require_admin()?;
+    let mut users: Vec<UserConnectionCount> = state
+        .presence
+        .list(false)
+        .into_iter()
+        .map(|u| UserConnectionCount { user_id: u.user_id, connections: u.connection_count })
+        .collect();
+    users.sort_by_key(|u| std::cmp::Reverse(u.connections));
+    Ok(Json(ConnectionCounts {
+        total: state.presence.total_connections(),
+        max_connections: state.ws_config.max_connections,
+        max_connections_per_user: state.ws_config.max_connections_per_user,
+        limit_policy: state.ws_config.limit_policy,
+        users,
+    }))
+}
\ No newline at end of file
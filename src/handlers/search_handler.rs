@@ -0,0 +1,73 @@
+use crate::{
+    auth::Claims,
+    error::AppError,
+    extract::ValidatedQuery,
+    models::{
+        pagination::DEFAULT_PAGE_SIZE,
+        search::{SearchHit, SearchKind, SearchQuery},
+    },
+    AppState,
+};
+use axum::{extract::State, Extension, Json};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[axum::debug_handler]
+pub async fn search(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<Claims>,
+    ValidatedQuery(query): ValidatedQuery<SearchQuery>,
+) -> Result<Json<Vec<SearchHit>>, AppError> {
+    let include_books = query.kind.is_none_or(|kind| kind == SearchKind::Book);
+    let include_authors = query.kind.is_none_or(|kind| kind == SearchKind::Author);
+    let include_emails = query.kind.is_none_or(|kind| kind == SearchKind::Email);
+
+    // Selain admin, user hanya melihat email yang dikirim atau diterimanya.
+    let is_admin = claims.is_admin();
+    let caller_email = if is_admin {
+        None
+    } else {
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+        sqlx::query_scalar!("SELECT email FROM users WHERE id = $1", user_id)
+            .fetch_optional(&state.db_pool)
+            .await?
+    };
+
+    let hits = sqlx::query_as!(
+        SearchHit,
+        r#"WITH q AS (SELECT websearch_to_tsquery('simple', $1) AS query)
+           SELECT 'book' AS "kind!", b.id AS "id!", b.title AS "title!",
+                  ts_headline('simple', html_escape(b.title || ' - ' || b.author), q.query,
+                              'StartSel=<mark>, StopSel=</mark>, MaxFragments=2') AS "snippet!",
+                  ts_rank(b.search_vector, q.query) AS "rank!"
+           FROM books b, q
+           WHERE $2 AND b.deleted_at IS NULL AND b.search_vector @@ q.query
+           UNION ALL
+           SELECT 'author', a.id, a.name,
+                  ts_headline('simple', html_escape(a.name || ' ' || coalesce(a.bio, '')), q.query,
+                              'StartSel=<mark>, StopSel=</mark>, MaxFragments=2'),
+                  ts_rank(a.search_vector, q.query)
+           FROM authors a, q
+           WHERE $7 AND a.search_vector @@ q.query
+           UNION ALL
+           SELECT 'email', e.id, e.subject,
+                  ts_headline('simple', html_escape(e.subject || ' ' || coalesce(e.body, '')), q.query,
+                              'StartSel=<mark>, StopSel=</mark>, MaxFragments=2'),
+                  ts_rank(e.search_vector, q.query)
+           FROM emails e, q
+           WHERE $3 AND e.deleted_at IS NULL AND e.search_vector @@ q.query
+             AND ($4 OR lower(e.sender) = lower($5) OR lower(e.recipient) = lower($5))
+           ORDER BY 5 DESC, 2
+           LIMIT $6"#,
+        query.q,
+        include_books,
+        include_emails,
+        is_admin,
+        caller_email,
+        query.limit.unwrap_or(DEFAULT_PAGE_SIZE),
+        include_authors
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+    Ok(Json(hits))
+}
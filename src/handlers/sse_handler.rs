@@ -0,0 +1,81 @@
+use crate::{auth::Claims, event_bus::SharedEvent, outbox, ws::codec::EncodedEvent, AppState};
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+    Extension,
+};
+use futures_util::stream::{self, Stream, StreamExt};
+use std::{collections::HashSet, convert::Infallible, sync::Arc, time::Duration};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+// Jumlah event maksimum yang diputar ulang untuk satu `Last-Event-ID`.
+const MAX_REPLAY: i64 = 1000;
+
+// Alternatif WebSocket untuk client di balik proxy yang memblokir upgrade.
+// Isi `data` sama persis dengan frame JSON WebSocket, dan `id` adalah id envelope.
+// Saat menyambung ulang, event sesudah `Last-Event-ID` diputar ulang dari outbox, sehingga
+// tetap berlaku setelah restart maupun lewat replika lain. Jika tidak bisa (id tidak
+// dikenal, sudah melewati masa retensi outbox, atau terlalu banyak yang terlewat), server
+// mengirim event `reset` dan client perlu memuat ulang datanya.
+#[axum::debug_handler]
+pub async fn event_stream(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let last_event_id = headers.get("last-event-id").map(|v| v.to_str().unwrap_or_default().trim().to_string());
+
+    // Berlangganan sebelum membaca outbox agar event yang terbit di antaranya tidak hilang;
+    // event yang ada di keduanya hanya dikirim sekali.
+    let rx = state.events.subscribe();
+    let (replay, reset) = match last_event_id.as_deref().map(Uuid::parse_str) {
+        None => (Vec::new(), false),
+        Some(Err(_)) => (Vec::new(), true),
+        Some(Ok(last_id)) => match outbox::replay_after(&state.db_pool, last_id, MAX_REPLAY).await {
+            Ok(Some(events)) => (events, false),
+            Ok(None) => (Vec::new(), true),
+            Err(e) => {
+                tracing::error!("Gagal memutar ulang event SSE sesudah {}: {:?}", last_id, e);
+                (Vec::new(), true)
+            }
+        },
+    };
+    tracing::info!("SSE client terhubung: {} (memutar ulang {} event, reset: {})", claims.sub, replay.len(), reset);
+
+    let replayed: Arc<HashSet<Uuid>> = Arc::new(replay.iter().map(|envelope| envelope.id).collect());
+    let reset_event = reset.then(|| {
+        let data = serde_json::json!({ "lastEventId": last_event_id }).to_string();
+        Ok(Event::default().event("reset").data(data))
+    });
+    let replay: Vec<SharedEvent> = replay.into_iter().map(|envelope| Arc::new(EncodedEvent::new(envelope))).collect();
+
+    let live = stream::unfold(rx, |mut rx| async move {
+        match rx.recv().await {
+            Ok(event) => Some((event, rx)),
+            // Akhiri stream agar client menyambung ulang dengan `Last-Event-ID`
+            // dan mengambil event yang terlewat dari outbox.
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("SSE client tertinggal {} event, koneksi ditutup", skipped);
+                None
+            }
+            Err(RecvError::Closed) => None,
+        }
+    })
+    .filter(move |event: &SharedEvent| std::future::ready(!replayed.contains(&event.value().id)));
+
+    // Stream berakhir saat shutdown agar graceful shutdown tidak menunggu sampai batas waktu.
+    let mut shutdown = state.shutdown.clone();
+    let stopping = async move {
+        let _ = shutdown.wait_for(|stopping| *stopping).await;
+    };
+
+    let events = stream::iter(replay).chain(live).filter_map(|event: SharedEvent| async move {
+        let json = event.json()?;
+        Some(Ok(Event::default().id(event.value().id.to_string()).data(json.as_str())))
+    });
+    let events = stream::iter(reset_event).chain(events).take_until(stopping);
+
+    Sse::new(events).keep_alive(KeepAlive::new().interval(Duration::from_secs(15)).text("keep-alive"))
+}
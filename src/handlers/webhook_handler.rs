@@ -0,0 +1,91 @@
+use crate::{auth::Claims, error::AppError, models::webhook::{CreateWebhook, UpdateWebhook, Webhook, WebhookDelivery}, AppState};
+use axum::{extract::{Path, State}, Extension, Json};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+// Semua endpoint webhook hanya untuk admin.
+
+#[axum::debug_handler]
+pub async fn get_all_webhooks(State(state): State<Arc<AppState>>, Extension(claims): Extension<Claims>) -> Result<Json<Vec<Webhook>>, AppError> {
+    claims.require_admin()?;
+    let webhooks = sqlx::query_as!(Webhook, "SELECT id, url, events, active, created_by, created_at, updated_at FROM webhooks ORDER BY created_at DESC")
+        .fetch_all(&state.db_pool)
+        .await?;
+    Ok(Json(webhooks))
+}
+
+#[axum::debug_handler]
+pub async fn create_webhook(State(state): State<Arc<AppState>>, Extension(claims): Extension<Claims>, Json(payload): Json<CreateWebhook>) -> Result<Json<Webhook>, AppError> {
+    claims.require_admin()?;
+    payload.validate()?;
+    let created_by = Uuid::parse_str(&claims.sub).map_err(|_| AppError::InvalidToken)?;
+    let webhook = sqlx::query_as!(
+        Webhook,
+        "INSERT INTO webhooks (url, events, secret, created_by) VALUES ($1, $2, $3, $4) RETURNING id, url, events, active, created_by, created_at, updated_at",
+        payload.url,
+        &payload.events,
+        payload.secret,
+        created_by
+    )
+    .fetch_one(&state.db_pool)
+    .await?;
+    Ok(Json(webhook))
+}
+
+#[axum::debug_handler]
+pub async fn get_webhook_by_id(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>) -> Result<Json<Webhook>, AppError> {
+    claims.require_admin()?;
+    let webhook = sqlx::query_as!(Webhook, "SELECT id, url, events, active, created_by, created_at, updated_at FROM webhooks WHERE id = $1", id)
+        .fetch_optional(&state.db_pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Webhook".to_string()))?;
+    Ok(Json(webhook))
+}
+
+#[axum::debug_handler]
+pub async fn update_webhook(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>, Json(payload): Json<UpdateWebhook>) -> Result<Json<Webhook>, AppError> {
+    claims.require_admin()?;
+    payload.validate()?;
+    let webhook = sqlx::query_as!(
+        Webhook,
+        r#"UPDATE webhooks
+           SET url = COALESCE($1, url), events = COALESCE($2, events), secret = COALESCE($3, secret),
+               active = COALESCE($4, active), updated_at = NOW()
+           WHERE id = $5 RETURNING id, url, events, active, created_by, created_at, updated_at"#,
+        payload.url,
+        payload.events.as_deref(),
+        payload.secret,
+        payload.active,
+        id
+    )
+    .fetch_optional(&state.db_pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Webhook".to_string()))?;
+    Ok(Json(webhook))
+}
+
+#[axum::debug_handler]
+pub async fn delete_webhook(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>) -> Result<(), AppError> {
+    claims.require_admin()?;
+    let result = sqlx::query!("DELETE FROM webhooks WHERE id = $1", id)
+        .execute(&state.db_pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Webhook".to_string()));
+    }
+    Ok(())
+}
+
+#[axum::debug_handler]
+pub async fn get_webhook_deliveries(State(state): State<Arc<AppState>>, Path(id): Path<Uuid>, Extension(claims): Extension<Claims>) -> Result<Json<Vec<WebhookDelivery>>, AppError> {
+    claims.require_admin()?;
+    let deliveries = sqlx::query_as!(
+        WebhookDelivery,
+        "SELECT * FROM webhook_deliveries WHERE webhook_id = $1 ORDER BY created_at DESC LIMIT 100",
+        id
+    )
+    .fetch_all(&state.db_pool)
+    .await?;
+    Ok(Json(deliveries))
+}
@@ -1,16 +1,39 @@
 use crate::auth::Claims;
+use crate::error::AppError;
+use crate::presence::LimitExceeded;
 use crate::ws;
+use crate::AppState;
 use axum::{
-    extract::ws::{WebSocket, WebSocketUpgrade},
+    extract::{ws::{WebSocket, WebSocketUpgrade}, State},
     response::Response,
     Extension,
 };
+use std::sync::Arc;
 
 #[axum::debug_handler]
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
     Extension(claims): Extension<Claims>,
-) -> Response {
-    let user_id = claims.sub.clone();
-    ws.on_upgrade(move |socket: WebSocket| ws::handle_socket(socket, user_id))
+) -> Result<Response, AppError> {
+    if *state.shutdown.borrow() {
+        return Err(AppError::ServiceUnavailable("Server sedang dimatikan".to_string()));
+    }
+    // Slot didaftarkan sebelum upgrade agar batas koneksi tidak bisa dilewati oleh
+    // banyak upgrade yang berjalan bersamaan.
+    let queue = ws::new_queue(&state.ws_config);
+    let slot = state
+        .presence
+        .connect(&claims.sub, queue.clone(), &state.ws_config)
+        .map_err(|e| match e {
+            LimitExceeded::PerUser(max) => AppError::TooManyRequests(format!(
+                "Batas {} koneksi WebSocket per user telah tercapai", max
+            )),
+            LimitExceeded::Global(max) => AppError::ServiceUnavailable(format!(
+                "Server telah mencapai batas {} koneksi WebSocket", max
+            )),
+        })?;
+    Ok(ws
+        .protocols(ws::codec::PROTOCOLS)
+        .on_upgrade(move |socket: WebSocket| ws::handle_socket(socket, claims, slot, queue, state)))
 }
\ No newline at end of file
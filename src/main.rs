@@ -3,6 +3,7 @@ use dotenvy::dotenv;
 use sqlx::PgPool;
 use std::net::{SocketAddr, TcpListener}; // Gunakan TcpListener dari std
 use std::sync::Arc;
+use tokio::sync::watch;
 use tower_http::{
     compression::CompressionLayer,
     cors::{Any, CorsLayer},
@@ -11,17 +12,33 @@ use tower_http::{
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod auth;
+mod bulk;
 mod db;
 mod error;
+mod etag;
+mod event_bus;
+mod extract;
 mod handlers;
 mod models;
+mod outbox;
+mod presence;
 mod rate_limiter;
 mod routes;
+mod shutdown;
+mod trash;
+mod webhooks;
 mod ws;
 
 #[derive(Clone)]
 pub struct AppState {
     db_pool: PgPool,
+    events: Arc<dyn event_bus::EventBus>,
+    outbox: Arc<outbox::Outbox>,
+    presence: Arc<presence::PresenceRegistry>,
+    ws_config: ws::WsConfig,
+    // Bernilai true setelah sinyal shutdown diterima.
+    shutdown: watch::Receiver<bool>,
+    shutdown_config: shutdown::ShutdownConfig,
 }
 
 #[tokio::main]
@@ -37,8 +54,24 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     let db_pool = db::init_db_pool().await?;
+    db::run_migrations(&db_pool).await?;
+    let events = event_bus::create_event_bus(&db_pool).await?;
 
-    let app_state = Arc::new(AppState { db_pool });
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let shutdown_config = shutdown::ShutdownConfig::from_env();
+    let app_state = Arc::new(AppState {
+        db_pool,
+        events,
+        outbox: Arc::new(outbox::Outbox::default()),
+        presence: Arc::new(presence::PresenceRegistry::default()),
+        ws_config: ws::WsConfig::from_env(),
+        shutdown: shutdown_rx.clone(),
+        shutdown_config: shutdown_config.clone(),
+    });
+
+    outbox::spawn_relay(app_state.clone());
+    webhooks::spawn(app_state.clone(), webhooks::WebhookConfig::from_env());
+    trash::spawn_purge(app_state.db_pool.clone(), trash::TrashConfig::from_env());
 
     let governor_layer = rate_limiter::create_governor_layer();
 
@@ -54,7 +87,8 @@ async fn main() -> anyhow::Result<()> {
                     CorsLayer::new()
                         .allow_origin(Any)
                         .allow_methods(Any)
-                        .allow_headers(Any),
+                        .allow_headers(Any)
+                        .expose_headers([axum::http::header::ETAG]),
                 ),
         );
 
@@ -65,11 +99,44 @@ async fn main() -> anyhow::Result<()> {
     // Cara baru untuk menjalankan server di Axum 0.7+
     let listener = TcpListener::bind(addr)?;
     tracing::info!("🚀 Server listening on {}", listener.local_addr()?);
-    axum::serve(
-        tokio::net::TcpListener::from_std(listener)?,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .await?;
+    let listener = tokio::net::TcpListener::from_std(listener)?;
+    let mut server_shutdown = shutdown_rx;
+    let mut server = tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(async move {
+                let _ = server_shutdown.wait_for(|stopping| *stopping).await;
+            })
+            .await
+    });
+
+    tokio::select! {
+        result = &mut server => {
+            result??;
+            return Ok(());
+        }
+        _ = shutdown::wait_for_signal() => {}
+    }
+
+    // Berhenti menerima koneksi baru, lalu beri tahu setiap WebSocket dan stream SSE
+    // agar menutup diri, dan tunggu semuanya selesai sampai batas waktu.
+    let _ = shutdown_tx.send(true);
+    let drain = async {
+        let result = server.await;
+        while app_state.presence.total_connections() > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        result
+    };
+    match tokio::time::timeout(shutdown_config.timeout, drain).await {
+        Ok(result) => {
+            result??;
+            tracing::info!("Shutdown selesai");
+        }
+        Err(_) => tracing::warn!(
+            "Batas waktu shutdown {:?} terlampaui, koneksi yang tersisa diputus paksa",
+            shutdown_config.timeout
+        ),
+    }
 
     Ok(())
 }
@@ -0,0 +1,155 @@
+use super::pagination::{Cursor, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use super::patch::{validate_required_text, Patch};
+use crate::error::AppError;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+// Jumlah maksimum penulis dalam satu buku.
+pub const MAX_BOOK_AUTHORS: usize = 100;
+const MAX_NAME_LENGTH: usize = 200;
+
+#[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Author {
+    pub id: Uuid,
+    pub name: String,
+    pub bio: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct CreateAuthor {
+    #[validate(length(min = 1, max = 200, message = "Nama harus 1-200 karakter"))]
+    pub name: String,
+    pub bio: Option<String>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct UpdateAuthor {
+    #[validate(length(min = 1, max = 200, message = "Nama harus 1-200 karakter"))]
+    pub name: Option<String>,
+    pub bio: Option<String>,
+}
+
+// Body `PATCH /authors/{id}` (`application/merge-patch+json`); `"bio": null` menghapus bio.
+#[derive(Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorPatch {
+    #[serde(default)]
+    #[validate(custom(function = "validate_name_patch"))]
+    pub name: Patch<String>,
+    #[serde(default)]
+    pub bio: Patch<String>,
+}
+
+impl From<UpdateAuthor> for AuthorPatch {
+    fn from(update: UpdateAuthor) -> Self {
+        Self { name: update.name.into(), bio: update.bio.into() }
+    }
+}
+
+fn validate_name_patch(value: &Patch<String>) -> Result<(), ValidationError> {
+    validate_required_text(value)?;
+    match value {
+        Patch::Value(name) if name.chars().count() > MAX_NAME_LENGTH => {
+            let mut err = ValidationError::new("length");
+            err.message = Some("Nama harus 1-200 karakter".into());
+            Err(err)
+        }
+        _ => Ok(()),
+    }
+}
+
+// Body `POST /authors/{id}/merge`: penulis duplikat yang digabung ke penulis `{id}`.
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct MergeAuthors {
+    #[validate(length(min = 1, max = 100, message = "sourceIds harus berisi 1-100 penulis"))]
+    pub source_ids: Vec<Uuid>,
+}
+
+// Parameter query `GET /authors`.
+#[derive(Debug, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorQuery {
+    // Cocok dengan kata pada nama atau bio, atau potongan nama.
+    #[validate(length(min = 1, max = 200, message = "Kata kunci harus 1-200 karakter"))]
+    pub q: Option<String>,
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+impl AuthorQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    pub fn cursor(&self) -> Result<Option<Cursor>, AppError> {
+        self.cursor.as_deref().map(Cursor::decode).transpose()
+    }
+}
+
+// Peran penulis pada satu buku; disimpan sebagai teks di `book_authors.role`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorRole {
+    #[default]
+    Author,
+    Editor,
+    Translator,
+}
+
+impl AuthorRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuthorRole::Author => "author",
+            AuthorRole::Editor => "editor",
+            AuthorRole::Translator => "translator",
+        }
+    }
+}
+
+impl FromStr for AuthorRole {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "author" => Ok(AuthorRole::Author),
+            "editor" => Ok(AuthorRole::Editor),
+            "translator" => Ok(AuthorRole::Translator),
+            _ => Err(format!("Peran penulis tidak dikenal: {}", value)),
+        }
+    }
+}
+
+// Satu penulis pada buku, urut menurut `position`.
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookAuthor {
+    pub author_id: Uuid,
+    pub name: String,
+    pub role: AuthorRole,
+    pub position: i32,
+}
+
+// Payload event `BookAuthorsUpdated`: daftar penulis buku setelah diganti.
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookAuthors {
+    pub book_id: Uuid,
+    pub authors: Vec<BookAuthor>,
+}
+
+// Elemen body `PUT /books/{id}/authors`; urutan array menjadi urutan penulis.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct BookAuthorInput {
+    pub author_id: Uuid,
+    #[serde(default)]
+    pub role: AuthorRole,
+}
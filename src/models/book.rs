@@ -1,17 +1,32 @@
+use super::isbn::{validate_isbn, validate_isbn_patch};
+use super::pagination::{SortOrder, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use super::patch::{validate_required_text, Patch};
+use crate::error::AppError;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
-#[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow)]
+#[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Book {
     pub id: Uuid,
     pub title: String,
+    // Teks penulis bebas untuk tampilan; relasi penulis yang terstruktur ada di `book_authors`.
     pub author: String,
     pub publication_year: Option<i32>,
+    // ISBN-13 tanpa tanda hubung.
+    pub isbn: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub version: i64,
+    // User yang membuat buku; `None` untuk buku yang dibuat sebelum kepemilikan dicatat.
+    pub created_by: Option<Uuid>,
+    // Hanya terisi untuk buku di tempat sampah.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize, Validate)]
@@ -20,7 +35,12 @@ pub struct CreateBook {
     pub title: String,
     #[validate(length(min = 1, message = "Penulis tidak boleh kosong"))]
     pub author: String,
+    // Alias agar hasil export (camelCase) bisa diimpor ulang.
+    #[serde(alias = "publicationYear")]
     pub publication_year: Option<i32>,
+    // ISBN-10 atau ISBN-13; disimpan sebagai ISBN-13.
+    #[validate(custom(function = "validate_isbn"))]
+    pub isbn: Option<String>,
 }
 
 #[derive(Deserialize, Validate)]
@@ -30,4 +50,170 @@ pub struct UpdateBook {
     #[validate(length(min = 1, message = "Penulis tidak boleh kosong"))]
     pub author: Option<String>,
     pub publication_year: Option<i32>,
-}
\ No newline at end of file
+    #[validate(custom(function = "validate_isbn"))]
+    pub isbn: Option<String>,
+}
+
+// Body `PATCH /books/{id}` (`application/merge-patch+json`).
+#[derive(Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct BookPatch {
+    #[serde(default)]
+    #[validate(custom(function = "validate_required_text"))]
+    pub title: Patch<String>,
+    #[serde(default)]
+    #[validate(custom(function = "validate_required_text"))]
+    pub author: Patch<String>,
+    #[serde(default)]
+    pub publication_year: Patch<i32>,
+    #[serde(default)]
+    #[validate(custom(function = "validate_isbn_patch"))]
+    pub isbn: Patch<String>,
+}
+
+impl From<UpdateBook> for BookPatch {
+    fn from(update: UpdateBook) -> Self {
+        Self {
+            title: update.title.into(),
+            author: update.author.into(),
+            publication_year: update.publication_year.into(),
+            isbn: update.isbn.into(),
+        }
+    }
+}
+// Parameter query `GET /books`. Parameter yang tidak dikenal ditolak agar salah ketik
+// tidak diam-diam diabaikan.
+#[derive(Debug, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+#[validate(schema(function = "validate_book_query"))]
+pub struct BookQuery {
+    #[validate(length(min = 1, message = "Penulis tidak boleh kosong"))]
+    pub author: Option<String>,
+    // Potongan judul, tidak peka huruf besar/kecil.
+    #[validate(length(min = 1, message = "Judul tidak boleh kosong"))]
+    pub title: Option<String>,
+    pub year_from: Option<i32>,
+    pub year_to: Option<i32>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub sort: BookSort,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+}
+
+impl BookQuery {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    // Cursor hanya berlaku untuk urutan yang sama dengan halaman asalnya.
+    pub fn cursor(&self) -> Result<Option<BookCursor>, AppError> {
+        let Some(cursor) = self.cursor.as_deref() else {
+            return Ok(None);
+        };
+        let cursor = BookCursor::decode(cursor)?;
+        if cursor.sort != self.sort || cursor.order != self.order {
+            return Err(AppError::BadRequest("Cursor tidak cocok dengan urutan yang diminta".to_string()));
+        }
+        Ok(Some(cursor))
+    }
+}
+
+fn validate_book_query(query: &BookQuery) -> Result<(), ValidationError> {
+    let inverted = [
+        (
+            matches!((query.year_from, query.year_to), (Some(from), Some(to)) if from > to),
+            "year_from tidak boleh lebih besar dari year_to",
+        ),
+        (
+            matches!((query.created_after, query.created_before), (Some(a), Some(b)) if a > b),
+            "created_after tidak boleh setelah created_before",
+        ),
+        (
+            matches!((query.updated_after, query.updated_before), (Some(a), Some(b)) if a > b),
+            "updated_after tidak boleh setelah updated_before",
+        ),
+    ];
+    match inverted.into_iter().find(|(invalid, _)| *invalid) {
+        Some((_, message)) => {
+            let mut err = ValidationError::new("invalid_range");
+            err.message = Some(message.into());
+            Err(err)
+        }
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookSort {
+    Title,
+    Author,
+    PublicationYear,
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+}
+
+impl BookSort {
+    // Ekspresi ORDER BY; hanya berasal dari enum ini, tidak pernah dari input mentah.
+    // Tahun terbit yang kosong diurutkan sebagai nilai terkecil agar keyset tetap total.
+    pub fn sql(self) -> &'static str {
+        match self {
+            BookSort::Title => "title",
+            BookSort::Author => "author",
+            BookSort::PublicationYear => "COALESCE(publication_year, -2147483648)",
+            BookSort::CreatedAt => "created_at",
+            BookSort::UpdatedAt => "updated_at",
+        }
+    }
+
+    pub fn value(self, book: &Book) -> SortValue {
+        match self {
+            BookSort::Title => SortValue::Text(book.title.clone()),
+            BookSort::Author => SortValue::Text(book.author.clone()),
+            BookSort::PublicationYear => SortValue::Int(book.publication_year.unwrap_or(i32::MIN)),
+            BookSort::CreatedAt => SortValue::Time(book.created_at),
+            BookSort::UpdatedAt => SortValue::Time(book.updated_at),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortValue {
+    Text(String),
+    Int(i32),
+    Time(DateTime<Utc>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookCursor {
+    pub sort: BookSort,
+    pub order: SortOrder,
+    pub value: SortValue,
+    pub id: Uuid,
+}
+
+impl BookCursor {
+    pub fn new(sort: BookSort, order: SortOrder, book: &Book) -> Self {
+        Self { sort, order, value: sort.value(book), id: book.id }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(value: &str) -> Result<Self, AppError> {
+        URL_SAFE_NO_PAD
+            .decode(value)
+            .ok()
+            .and_then(|raw| serde_json::from_slice(&raw).ok())
+            .ok_or_else(|| AppError::BadRequest("Cursor tidak valid".to_string()))
+    }
+}
@@ -0,0 +1,78 @@
+use crate::bulk::BulkFormat;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+pub const DEFAULT_CHUNK_SIZE: usize = 500;
+pub const MAX_CHUNK_SIZE: usize = 5000;
+// Laporan hanya memuat sejumlah error pertama agar respons tetap kecil.
+pub const MAX_REPORTED_ERRORS: usize = 1000;
+
+// Parameter query `POST /books/import`. Format diambil dari `Content-Type`.
+#[derive(Debug, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct ImportQuery {
+    // Validasi dan coba INSERT, lalu rollback.
+    #[serde(default)]
+    pub dry_run: bool,
+    #[validate(range(min = 1, max = 5000, message = "chunk_size harus antara 1 dan 5000"))]
+    pub chunk_size: Option<usize>,
+}
+
+impl ImportQuery {
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE).clamp(1, MAX_CHUNK_SIZE)
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: BulkFormat,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total: usize,
+    pub imported: usize,
+    pub failed: usize,
+    pub errors: Vec<ImportRowError>,
+    pub errors_truncated: bool,
+    // Terisi jika body rusak sehingga import berhenti sebelum akhir; baris sebelumnya tetap diproses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aborted: Option<String>,
+    // Terisi jika event `BooksImported` gagal dicatat walau ada buku yang tersimpan.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_error: Option<String>,
+}
+
+impl ImportReport {
+    pub fn reject(&mut self, row: usize, message: String) {
+        self.failed += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(ImportRowError { row, message });
+        } else {
+            self.errors_truncated = true;
+        }
+    }
+}
+
+// `row` adalah nomor record data, mulai dari 1 (header CSV tidak dihitung).
+#[derive(Debug, Serialize)]
+pub struct ImportRowError {
+    pub row: usize,
+    pub message: String,
+}
+
+// Payload event `BooksImported`, satu per import yang menyimpan data.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BookImportSummary {
+    pub import_id: Uuid,
+    pub imported: usize,
+    pub failed: usize,
+}
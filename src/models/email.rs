@@ -1,9 +1,11 @@
+use super::patch::{validate_required_text, Patch};
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow)]
+#[derive(Serialize, Deserialize, Debug, Clone, sqlx::FromRow, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Email {
     pub id: Uuid,
@@ -12,6 +14,10 @@ pub struct Email {
     pub subject: String,
     pub body: Option<String>,
     pub sent_at: DateTime<Utc>,
+    pub version: i64,
+    // Hanya terisi untuk email di tempat sampah.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize, Validate)]
@@ -29,4 +35,21 @@ pub struct CreateEmail {
 pub struct UpdateEmail {
     pub subject: Option<String>,
     pub body: Option<String>,
+}
+
+// Body `PATCH /emails/{id}` (`application/merge-patch+json`); `"body": null` menghapus isi email.
+#[derive(Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct EmailPatch {
+    #[serde(default)]
+    #[validate(custom(function = "validate_required_text"))]
+    pub subject: Patch<String>,
+    #[serde(default)]
+    pub body: Patch<String>,
+}
+
+impl From<UpdateEmail> for EmailPatch {
+    fn from(update: UpdateEmail) -> Self {
+        Self { subject: update.subject.into(), body: update.body.into() }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,89 @@
+use super::patch::Patch;
+use validator::ValidationError;
+
+// Mengubah ISBN-10 atau ISBN-13 (boleh dengan tanda hubung atau spasi) menjadi ISBN-13
+// tanpa pemisah. `None` jika format atau checksum tidak valid.
+pub fn normalize(input: &str) -> Option<String> {
+    let isbn: String = input.chars().filter(|c| !matches!(c, '-' | ' ')).collect::<String>().to_ascii_uppercase();
+    match isbn.len() {
+        10 if is_valid_isbn10(&isbn) => Some(isbn10_to_isbn13(&isbn)),
+        13 if is_valid_isbn13(&isbn) => Some(isbn),
+        _ => None,
+    }
+}
+
+// Digit 1-9 berbobot 10..2, digit terakhir (boleh `X` = 10) berbobot 1; total habis dibagi 11.
+fn is_valid_isbn10(isbn: &str) -> bool {
+    let mut sum = 0;
+    for (i, c) in isbn.chars().enumerate() {
+        let digit = match c {
+            '0'..='9' => c as u32 - '0' as u32,
+            'X' if i == 9 => 10,
+            _ => return false,
+        };
+        sum += digit * (10 - i as u32);
+    }
+    sum.is_multiple_of(11)
+}
+
+// Bobot bergantian 1 dan 3; total habis dibagi 10. Hanya awalan GS1 buku (978/979).
+fn is_valid_isbn13(isbn: &str) -> bool {
+    if !isbn.bytes().all(|b| b.is_ascii_digit()) || !(isbn.starts_with("978") || isbn.starts_with("979")) {
+        return false;
+    }
+    isbn13_sum(isbn).is_multiple_of(10)
+}
+
+fn isbn13_sum(digits: &str) -> u32 {
+    digits
+        .bytes()
+        .enumerate()
+        .map(|(i, b)| (b - b'0') as u32 * if i % 2 == 0 { 1 } else { 3 })
+        .sum()
+}
+
+fn isbn10_to_isbn13(isbn10: &str) -> String {
+    let body = format!("978{}", &isbn10[..9]);
+    let check = (10 - isbn13_sum(&body) % 10) % 10;
+    format!("{}{}", body, check)
+}
+
+pub fn validate_isbn(value: &str) -> Result<(), ValidationError> {
+    if normalize(value).is_some() {
+        return Ok(());
+    }
+    let mut err = ValidationError::new("isbn");
+    err.message = Some("ISBN-10 atau ISBN-13 tidak valid".into());
+    Err(err)
+}
+
+pub fn validate_isbn_patch(value: &Patch<String>) -> Result<(), ValidationError> {
+    match value {
+        Patch::Value(isbn) => validate_isbn(isbn),
+        Patch::Absent | Patch::Null => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_isbn10_to_isbn13() {
+        assert_eq!(normalize("0-306-40615-2").as_deref(), Some("9780306406157"));
+        assert_eq!(normalize("080442957x").as_deref(), Some("9780804429573"));
+    }
+
+    #[test]
+    fn keeps_valid_isbn13() {
+        assert_eq!(normalize("978-0-306-40615-7").as_deref(), Some("9780306406157"));
+        assert_eq!(normalize("979 10 90636 07 1").as_deref(), Some("9791090636071"));
+    }
+
+    #[test]
+    fn rejects_bad_checksums_and_formats() {
+        for input in ["0-306-40615-3", "978-0-306-40615-8", "123456789", "X306406152", "9770306406156", ""] {
+            assert_eq!(normalize(input), None, "{} seharusnya ditolak", input);
+        }
+    }
+}
@@ -1,3 +1,10 @@
+pub mod author;
 pub mod book;
+pub mod bulk;
 pub mod email;
-pub mod user;
\ No newline at end of file
+pub mod isbn;
+pub mod pagination;
+pub mod patch;
+pub mod search;
+pub mod user;
+pub mod webhook;
\ No newline at end of file
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use validator::ValidationError;
+
+// Nilai satu field pada JSON Merge Patch (RFC 7396): field yang tidak dikirim
+// dibiarkan, `null` menghapus nilai, selain itu nilai baru.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Patch<T> {
+    #[default]
+    Absent,
+    Null,
+    Value(T),
+}
+
+// Hanya dipanggil untuk field yang ada di body; field yang tidak ada memakai
+// `#[serde(default)]` sehingga menjadi `Absent`.
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Patch<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Option::<T>::deserialize(deserializer).map(|value| value.map_or(Patch::Null, Patch::Value))
+    }
+}
+
+// Dipakai validator untuk menyertakan nilai field pada pesan error.
+impl<T: Serialize> Serialize for Patch<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Patch::Value(value) => value.serialize(serializer),
+            Patch::Absent | Patch::Null => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<T> Patch<T> {
+    // `Some(nilai baru)` bila field diubah (termasuk menjadi null), `None` bila dibiarkan.
+    pub fn into_change(self) -> Option<Option<T>> {
+        match self {
+            Patch::Absent => None,
+            Patch::Null => Some(None),
+            Patch::Value(value) => Some(Some(value)),
+        }
+    }
+}
+
+// Body `PUT` lama memakai `Option`: field yang tidak dikirim berarti tidak diubah.
+impl<T> From<Option<T>> for Patch<T> {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Patch::Absent, Patch::Value)
+    }
+}
+
+// Untuk kolom NOT NULL: boleh tidak dikirim, tetapi tidak boleh `null` atau string kosong.
+pub fn validate_required_text(value: &Patch<String>) -> Result<(), ValidationError> {
+    let message = match value {
+        Patch::Null => "tidak boleh null",
+        Patch::Value(text) if text.is_empty() => "tidak boleh kosong",
+        _ => return Ok(()),
+    };
+    let mut err = ValidationError::new("required");
+    err.message = Some(message.into());
+    Err(err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Body {
+        #[serde(default)]
+        field: Patch<i32>,
+    }
+
+    fn field(json: &str) -> Patch<i32> {
+        serde_json::from_str::<Body>(json).unwrap().field
+    }
+
+    #[test]
+    fn distinguishes_absent_null_and_value() {
+        assert_eq!(field("{}"), Patch::Absent);
+        assert_eq!(field(r#"{"field": null}"#), Patch::Null);
+        assert_eq!(field(r#"{"field": 5}"#), Patch::Value(5));
+        assert!(serde_json::from_str::<Body>(r#"{"field": "lima"}"#).is_err());
+    }
+
+    #[test]
+    fn into_change_and_from_option() {
+        assert_eq!(Patch::<i32>::Absent.into_change(), None);
+        assert_eq!(Patch::<i32>::Null.into_change(), Some(None));
+        assert_eq!(Patch::Value(5).into_change(), Some(Some(5)));
+        assert_eq!(Patch::from(None::<i32>), Patch::Absent);
+        assert_eq!(Patch::from(Some(5)), Patch::Value(5));
+    }
+
+    #[test]
+    fn required_text_rejects_null_and_empty() {
+        assert!(validate_required_text(&Patch::Absent).is_ok());
+        assert!(validate_required_text(&Patch::Value("Judul".to_string())).is_ok());
+        assert!(validate_required_text(&Patch::Null).is_err());
+        assert!(validate_required_text(&Patch::Value(String::new())).is_err());
+    }
+}
@@ -0,0 +1,37 @@
+use super::pagination::MAX_PAGE_SIZE;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct SearchQuery {
+    // Sintaks seperti mesin pencari: `"frasa persis"`, `or`, `-kata`.
+    #[validate(length(min = 1, max = 200, message = "Kata kunci harus 1-200 karakter"))]
+    pub q: String,
+    pub kind: Option<SearchKind>,
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, message = "limit di luar rentang"))]
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    Book,
+    Author,
+    Email,
+}
+
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    // `book`, `author` atau `email`.
+    pub kind: String,
+    pub id: Uuid,
+    // Judul buku, nama penulis atau subjek email.
+    pub title: String,
+    // Potongan teks dengan kata yang cocok dibungkus `<mark>`; teks asli sudah di-escape
+    // sebagai HTML, jadi aman dirender langsung.
+    pub snippet: String,
+    pub rank: f32,
+}
@@ -0,0 +1,65 @@
+use crate::ws::WsEvent;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    pub events: Vec<String>,
+    pub active: bool,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct CreateWebhook {
+    #[validate(url(message = "URL tidak valid"))]
+    pub url: String,
+    #[serde(default)]
+    #[validate(custom(function = "validate_event_names"))]
+    pub events: Vec<String>,
+    #[validate(length(min = 16, message = "Secret minimal 16 karakter"))]
+    pub secret: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct UpdateWebhook {
+    #[validate(url(message = "URL tidak valid"))]
+    pub url: Option<String>,
+    #[validate(custom(function = "validate_event_names"))]
+    pub events: Option<Vec<String>>,
+    #[validate(length(min = 16, message = "Secret minimal 16 karakter"))]
+    pub secret: Option<String>,
+    pub active: Option<bool>,
+}
+
+#[derive(Serialize, Debug, Clone, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+fn validate_event_names(events: &[String]) -> Result<(), ValidationError> {
+    if events.iter().all(|e| WsEvent::NAMES.contains(&e.as_str())) {
+        Ok(())
+    } else {
+        let mut err = ValidationError::new("unknown_event");
+        err.message = Some(format!("Event yang dikenal: {}", WsEvent::NAMES.join(", ")).into());
+        Err(err)
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{error::AppError, ws::envelope::EventEnvelope, AppState};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+// Kunci advisory lock transaksi agar hanya satu relay yang menerbitkan pada satu waktu,
+// sehingga urutan terbit sama di semua replika.
+const RELAY_LOCK_KEY: i64 = 0x7773_7300_0002;
+const BATCH_SIZE: i64 = 100;
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const RETENTION_HOURS: i32 = 24;
+
+// Transactional outbox: handler menulis event di transaksi yang sama dengan INSERT/UPDATE/DELETE,
+// dan relay menerbitkannya setelah commit. Event tidak hilang bila proses mati di antara
+// commit dan publish, dan tidak pernah terkirim untuk transaksi yang di-rollback.
+#[derive(Default)]
+pub struct Outbox {
+    wake: Notify,
+}
+
+impl Outbox {
+    // Dipanggil setelah commit agar relay tidak perlu menunggu interval polling.
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+}
+
+pub async fn enqueue(tx: &mut Transaction<'_, Postgres>, envelope: &EventEnvelope) -> Result<(), AppError> {
+    let payload = serde_json::to_value(envelope).map_err(|e| AppError::InternalServerError(e.into()))?;
+    sqlx::query!(
+        "INSERT INTO event_outbox (event_id, payload) VALUES ($1, $2)",
+        envelope.id,
+        payload
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+// Event sesudah `last_event_id` dalam urutan terbit relay, paling banyak `limit`, untuk
+// diputar ulang (misalnya SSE `Last-Event-ID`). Hanya baris yang sudah boleh diterbitkan
+// yang diambil, jadi hasilnya selalu awalan dari urutan yang dilihat subscriber live.
+// `None` jika id tidak lagi ada di outbox (tidak dikenal atau sudah dibersihkan setelah
+// masa retensi) atau event sesudahnya lebih dari `limit`.
+pub async fn replay_after(pool: &PgPool, last_event_id: Uuid, limit: i64) -> anyhow::Result<Option<Vec<EventEnvelope>>> {
+    let known = sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM event_outbox WHERE event_id = $1)", last_event_id)
+        .fetch_one(pool)
+        .await?;
+    if known != Some(true) {
+        return Ok(None);
+    }
+    let rows = sqlx::query_scalar!(
+        "SELECT o.payload FROM event_outbox last, event_outbox o
+         WHERE last.event_id = $1 AND (o.xact_id, o.seq) > (last.xact_id, last.seq)
+           AND o.xact_id < pg_snapshot_xmin(pg_current_snapshot())
+         ORDER BY o.xact_id, o.seq
+         LIMIT $2",
+        last_event_id,
+        limit + 1
+    )
+    .fetch_all(pool)
+    .await?;
+    if rows.len() as i64 > limit {
+        return Ok(None);
+    }
+    let events = rows.into_iter().map(serde_json::from_value).collect::<Result<_, _>>()?;
+    Ok(Some(events))
+}
+
+pub fn spawn_relay(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut last_purge = tokio::time::Instant::now();
+        loop {
+            match relay_batch(&state).await {
+                // Masih ada sisa antrean, langsung ambil batch berikutnya.
+                Ok(count) if count as i64 == BATCH_SIZE => continue,
+                Ok(_) => {}
+                Err(e) => tracing::error!("Relay outbox gagal: {:?}", e),
+            }
+            if last_purge.elapsed() > Duration::from_secs(600) {
+                purge_delivered(&state).await;
+                last_purge = tokio::time::Instant::now();
+            }
+            tokio::select! {
+                _ = state.outbox.wake.notified() => {}
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            }
+        }
+    });
+}
+
+async fn relay_batch(state: &AppState) -> anyhow::Result<usize> {
+    let mut tx = state.db_pool.begin().await?;
+    let locked = sqlx::query_scalar!("SELECT pg_try_advisory_xact_lock($1)", RELAY_LOCK_KEY)
+        .fetch_one(&mut *tx)
+        .await?;
+    if locked != Some(true) {
+        return Ok(0);
+    }
+
+    // `seq` dialokasikan saat INSERT, bukan saat commit, sehingga baris dengan `seq` kecil
+    // bisa muncul setelah baris yang lebih besar sudah diterbitkan. Karena itu hanya baris
+    // dari transaksi yang lebih tua dari xmin snapshot (semuanya sudah selesai) yang diambil,
+    // diurutkan per transaksi: baris yang muncul kemudian selalu berada di belakangnya.
+    // Transaksi tulis yang lama berjalan menunda relay sampai transaksi itu selesai.
+    let rows = sqlx::query!(
+        "SELECT seq, payload FROM event_outbox
+         WHERE delivered_at IS NULL AND xact_id < pg_snapshot_xmin(pg_current_snapshot())
+         ORDER BY xact_id, seq
+         LIMIT $1",
+        BATCH_SIZE
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    if rows.is_empty() {
+        return Ok(0);
+    }
+
+    // Jika proses mati sebelum commit, baris yang sama akan diterbitkan ulang
+    // (at-least-once); client dapat menghapus duplikat lewat id envelope. Berhenti pada
+    // publish pertama yang gagal agar event berikutnya tidak mendahuluinya.
+    let mut seqs = Vec::with_capacity(rows.len());
+    let mut failure = None;
+    for row in &rows {
+        match serde_json::from_value::<EventEnvelope>(row.payload.clone()) {
+            Ok(envelope) => {
+                if let Err(e) = state.events.publish(envelope).await {
+                    failure = Some(e.context(format!("gagal menerbitkan event outbox {}", row.seq)));
+                    break;
+                }
+            }
+            // Payload rusak tidak akan pernah bisa diterbitkan, jadi ikut ditandai.
+            Err(e) => tracing::error!("Payload outbox {} tidak valid, dilewati: {}", row.seq, e),
+        }
+        seqs.push(row.seq);
+    }
+
+    // Tandai hanya baris yang benar-benar diterbitkan; sisanya dicoba lagi pada batch berikutnya.
+    sqlx::query!("UPDATE event_outbox SET delivered_at = NOW() WHERE seq = ANY($1)", &seqs)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(seqs.len()),
+    }
+}
+
+async fn purge_delivered(state: &AppState) {
+    let result = sqlx::query!(
+        "DELETE FROM event_outbox WHERE delivered_at < NOW() - make_interval(hours => $1)",
+        RETENTION_HOURS
+    )
+    .execute(&state.db_pool)
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("Gagal membersihkan outbox: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws::{envelope::EventContext, WsEvent};
+
+    // Baris uji langsung ditandai terkirim agar tidak diterbitkan relay yang sedang berjalan,
+    // lalu dihapus di akhir. Dilewati jika `DATABASE_URL` tidak diatur.
+    #[tokio::test]
+    async fn replays_events_after_the_given_id_in_relay_order() {
+        let Ok(url) = std::env::var("DATABASE_URL") else { return };
+        let pool = PgPool::connect(&url).await.unwrap();
+        let ctx = EventContext::for_user("uji-replay");
+        let envelopes: Vec<EventEnvelope> = (0..3).map(|_| ctx.envelope(WsEvent::AuthorDeleted(Uuid::new_v4()))).collect();
+        for envelope in &envelopes {
+            let mut tx = pool.begin().await.unwrap();
+            enqueue(&mut tx, envelope).await.unwrap();
+            sqlx::query!("UPDATE event_outbox SET delivered_at = NOW() WHERE event_id = $1", envelope.id)
+                .execute(&mut *tx)
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+        }
+        let ids: Vec<Uuid> = envelopes.iter().map(|envelope| envelope.id).collect();
+
+        let replayed = replay_after(&pool, ids[0], MAX_TEST_REPLAY).await.unwrap().unwrap();
+        let ours: Vec<Uuid> = replayed.iter().map(|envelope| envelope.id).filter(|id| ids.contains(id)).collect();
+        assert_eq!(ours, ids[1..]);
+        assert!(replay_after(&pool, ids[2], MAX_TEST_REPLAY).await.unwrap().unwrap().iter().all(|e| !ids.contains(&e.id)));
+        // Lebih banyak dari batas, atau id tidak dikenal: client harus memuat ulang.
+        assert!(replay_after(&pool, ids[0], 1).await.unwrap().is_none());
+        assert!(replay_after(&pool, Uuid::new_v4(), MAX_TEST_REPLAY).await.unwrap().is_none());
+
+        sqlx::query!("DELETE FROM event_outbox WHERE event_id = ANY($1)", &ids)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    const MAX_TEST_REPLAY: i64 = 1000;
+}
@@ -0,0 +1,228 @@
+use crate::ws::{
+    outbound::{OutboundQueue, QueueMetrics, CLOSE_CONNECTION_LIMIT},
+    LimitPolicy, WsConfig,
+};
+use axum::extract::ws::CloseFrame;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+// Registry koneksi WebSocket yang sedang aktif, dikelompokkan per user.
+#[derive(Default)]
+pub struct PresenceRegistry {
+    inner: RwLock<Registry>,
+}
+
+#[derive(Default)]
+struct Registry {
+    users: HashMap<String, UserPresence>,
+    total: usize,
+}
+
+struct UserPresence {
+    connected_since: DateTime<Utc>,
+    last_activity: DateTime<Utc>,
+    connections: HashMap<Uuid, ConnectionEntry>,
+}
+
+struct ConnectionEntry {
+    info: ConnectionInfo,
+    queue: Arc<OutboundQueue>,
+}
+
+#[derive(Debug)]
+pub enum LimitExceeded {
+    Global(usize),
+    PerUser(usize),
+}
+
+// Slot koneksi yang sudah terdaftar. Jika upgrade WebSocket gagal dan slot dibuang
+// tanpa `release`, koneksi otomatis dihapus dari registry.
+pub struct ConnectionSlot {
+    registry: Arc<PresenceRegistry>,
+    user_id: String,
+    connection_id: Uuid,
+    first: bool,
+    released: bool,
+}
+
+impl ConnectionSlot {
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    pub fn connection_id(&self) -> Uuid {
+        self.connection_id
+    }
+
+    // True jika ini koneksi pertama user tersebut.
+    pub fn is_first(&self) -> bool {
+        self.first
+    }
+
+    // Menghapus koneksi. Mengembalikan Some jika user tidak lagi memiliki koneksi aktif.
+    pub fn release(mut self) -> Option<OfflineUser> {
+        self.released = true;
+        self.registry.disconnect(&self.user_id, self.connection_id)
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        if !self.released {
+            self.registry.disconnect(&self.user_id, self.connection_id);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionInfo {
+    pub connection_id: Uuid,
+    pub connected_at: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue: Option<QueueMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OnlineUser {
+    pub user_id: String,
+    pub connection_count: usize,
+    pub connected_since: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connections: Option<Vec<ConnectionInfo>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineUser {
+    pub user_id: String,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl PresenceRegistry {
+    // Mendaftarkan koneksi baru sambil menegakkan batas koneksi global dan per user.
+    // Batas global selalu menolak; batas per user menolak atau mengusir koneksi
+    // tertua user tersebut sesuai `limit_policy`.
+    pub fn connect(self: &Arc<Self>, user_id: &str, queue: Arc<OutboundQueue>, config: &WsConfig) -> Result<ConnectionSlot, LimitExceeded> {
+        let now = Utc::now();
+        let connection_id = Uuid::new_v4();
+        let mut inner = self.inner.write().unwrap();
+        let Registry { users, total } = &mut *inner;
+
+        let user_count = users.get(user_id).map_or(0, |p| p.connections.len());
+        let evict = user_count >= config.max_connections_per_user;
+        if evict && config.limit_policy == LimitPolicy::Reject {
+            return Err(LimitExceeded::PerUser(config.max_connections_per_user));
+        }
+        if !evict && *total >= config.max_connections {
+            return Err(LimitExceeded::Global(config.max_connections));
+        }
+
+        let first = user_count == 0;
+        let presence = users.entry(user_id.to_string()).or_insert_with(|| UserPresence {
+            connected_since: now,
+            last_activity: now,
+            connections: HashMap::new(),
+        });
+        if evict {
+            // Koneksi yang diusir langsung dihapus dari registry agar slotnya bisa dipakai;
+            // `disconnect` miliknya nanti tidak berpengaruh apa-apa.
+            let oldest = presence
+                .connections
+                .values()
+                .min_by_key(|conn| conn.info.connected_at)
+                .map(|conn| conn.info.connection_id);
+            if let Some(entry) = oldest.and_then(|id| presence.connections.remove(&id)) {
+                *total -= 1;
+                entry.queue.close(Some(CloseFrame {
+                    code: CLOSE_CONNECTION_LIMIT,
+                    reason: "connection limit reached".into(),
+                }));
+                tracing::info!("Koneksi {} milik {} diusir karena batas koneksi", entry.info.connection_id, user_id);
+            }
+        }
+        presence.last_activity = now;
+        let info = ConnectionInfo { connection_id, connected_at: now, last_activity: now, queue: None };
+        presence.connections.insert(connection_id, ConnectionEntry { info, queue });
+        *total += 1;
+
+        Ok(ConnectionSlot {
+            registry: self.clone(),
+            user_id: user_id.to_string(),
+            connection_id,
+            first,
+            released: false,
+        })
+    }
+
+    pub fn total_connections(&self) -> usize {
+        self.inner.read().unwrap().total
+    }
+
+    pub fn touch(&self, user_id: &str, connection_id: Uuid) {
+        let now = Utc::now();
+        let mut inner = self.inner.write().unwrap();
+        if let Some(presence) = inner.users.get_mut(user_id) {
+            presence.last_activity = now;
+            if let Some(conn) = presence.connections.get_mut(&connection_id) {
+                conn.info.last_activity = now;
+            }
+        }
+    }
+
+    fn disconnect(&self, user_id: &str, connection_id: Uuid) -> Option<OfflineUser> {
+        let mut inner = self.inner.write().unwrap();
+        let Registry { users, total } = &mut *inner;
+        let presence = users.get_mut(user_id)?;
+        if presence.connections.remove(&connection_id).is_some() {
+            *total -= 1;
+        }
+        if !presence.connections.is_empty() {
+            return None;
+        }
+        let last_seen = presence.last_activity;
+        users.remove(user_id);
+        Some(OfflineUser { user_id: user_id.to_string(), last_seen })
+    }
+
+    pub fn get(&self, user_id: &str, include_connections: bool) -> Option<OnlineUser> {
+        let inner = self.inner.read().unwrap();
+        inner.users.get(user_id).map(|p| to_online_user(user_id, p, include_connections))
+    }
+
+    pub fn list(&self, include_connections: bool) -> Vec<OnlineUser> {
+        let inner = self.inner.read().unwrap();
+        let mut online: Vec<OnlineUser> = inner
+            .users
+            .iter()
+            .map(|(user_id, p)| to_online_user(user_id, p, include_connections))
+            .collect();
+        online.sort_by_key(|u| u.connected_since);
+        online
+    }
+}
+
+fn to_online_user(user_id: &str, presence: &UserPresence, include_connections: bool) -> OnlineUser {
+    OnlineUser {
+        user_id: user_id.to_string(),
+        connection_count: presence.connections.len(),
+        connected_since: presence.connected_since,
+        last_activity: presence.last_activity,
+        connections: include_connections.then(|| {
+            let mut conns: Vec<ConnectionInfo> = presence
+                .connections
+                .values()
+                .map(|conn| ConnectionInfo { queue: Some(conn.queue.stats().snapshot()), ..conn.info.clone() })
+                .collect();
+            conns.sort_by_key(|c| c.connected_at);
+            conns
+        }),
+    }
+}
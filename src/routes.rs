@@ -1,6 +1,6 @@
 use crate::{
     auth::auth_middleware,
-    handlers::{auth_handler, book_handler, email_handler, ws_handler},
+    handlers::{auth_handler, author_handler, book_bulk_handler, book_handler, docs_handler, email_handler, presence_handler, search_handler, sse_handler, webhook_handler, ws_handler},
     AppState,
 };
 use axum::{
@@ -16,12 +16,22 @@ pub fn create_router(app_state: Arc<AppState>) -> Router {
 
 fn api_routes(app_state: Arc<AppState>) -> Router {
     Router::new()
+        .merge(create_docs_routes())
         .merge(create_auth_routes(app_state.clone()))
         .merge(create_book_routes(app_state.clone()))
+        .merge(create_author_routes(app_state.clone()))
         .merge(create_email_routes(app_state.clone()))
+        .merge(create_search_routes(app_state.clone()))
+        .merge(create_presence_routes(app_state.clone()))
+        .merge(create_webhook_routes(app_state.clone()))
+        .merge(create_sse_route(app_state.clone()))
         .merge(create_ws_route(app_state))
 }
 
+fn create_docs_routes() -> Router {
+    Router::new().route("/asyncapi.json", get(docs_handler::get_asyncapi))
+}
+
 fn create_auth_routes(app_state: Arc<AppState>) -> Router {
     Router::new()
         .route("/signup", post(auth_handler::signup))
@@ -32,12 +42,35 @@ fn create_auth_routes(app_state: Arc<AppState>) -> Router {
 fn create_book_routes(app_state: Arc<AppState>) -> Router {
     Router::new()
         .route("/books", get(book_handler::get_all_books).post(book_handler::create_book))
+        .route("/books/trash", get(book_handler::get_trashed_books))
+        .route("/books/import", post(book_bulk_handler::import_books))
+        .route("/books/export", get(book_bulk_handler::export_books))
+        .route("/books/isbn/{isbn}", get(book_handler::get_book_by_isbn))
         .route(
-            "/books/:id",
+            "/books/{id}",
             get(book_handler::get_book_by_id)
                 .put(book_handler::update_book)
+                .patch(book_handler::patch_book)
                 .delete(book_handler::delete_book),
         )
+        .route("/books/{id}/restore", post(book_handler::restore_book))
+        .route("/books/{id}/authors", get(author_handler::get_book_authors).put(author_handler::set_book_authors))
+        .route_layer(middleware::from_fn(auth_middleware))
+        .with_state(app_state)
+}
+
+fn create_author_routes(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/authors", get(author_handler::get_all_authors).post(author_handler::create_author))
+        .route(
+            "/authors/{id}",
+            get(author_handler::get_author_by_id)
+                .put(author_handler::update_author)
+                .patch(author_handler::patch_author)
+                .delete(author_handler::delete_author),
+        )
+        .route("/authors/{id}/books", get(author_handler::get_author_books))
+        .route("/authors/{id}/merge", post(author_handler::merge_authors))
         .route_layer(middleware::from_fn(auth_middleware))
         .with_state(app_state)
 }
@@ -45,17 +78,56 @@ fn create_book_routes(app_state: Arc<AppState>) -> Router {
 fn create_email_routes(app_state: Arc<AppState>) -> Router {
     Router::new()
         .route("/emails", get(email_handler::get_all_emails).post(email_handler::create_email))
+        .route("/emails/trash", get(email_handler::get_trashed_emails))
         .route(
-            "/emails/:id",
+            "/emails/{id}",
             get(email_handler::get_email_by_id)
                 .put(email_handler::update_email)
+                .patch(email_handler::patch_email)
                 .delete(email_handler::delete_email),
         )
+        .route("/emails/{id}/restore", post(email_handler::restore_email))
         // This line adds the authentication requirement
         .route_layer(middleware::from_fn(auth_middleware))
         .with_state(app_state)
 }
 
+fn create_search_routes(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/search", get(search_handler::search))
+        .route_layer(middleware::from_fn(auth_middleware))
+        .with_state(app_state)
+}
+
+fn create_presence_routes(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/users/online", get(presence_handler::get_online_users))
+        .route("/ws/connections", get(presence_handler::get_connection_counts))
+        .route_layer(middleware::from_fn(auth_middleware))
+        .with_state(app_state)
+}
+
+fn create_webhook_routes(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/webhooks", get(webhook_handler::get_all_webhooks).post(webhook_handler::create_webhook))
+        .route(
+            "/webhooks/{id}",
+            get(webhook_handler::get_webhook_by_id)
+                .put(webhook_handler::update_webhook)
+                .delete(webhook_handler::delete_webhook),
+        )
+        .route("/webhooks/{id}/deliveries", get(webhook_handler::get_webhook_deliveries))
+        .route_layer(middleware::from_fn(auth_middleware))
+        .with_state(app_state)
+}
+
+fn create_sse_route(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/events", get(sse_handler::event_stream))
+        .route_layer(middleware::from_fn(auth_middleware))
+        .with_state(app_state)
+}
+
 fn create_ws_route(app_state: Arc<AppState>) -> Router {
     Router::new()
         .route("/ws", get(ws_handler::websocket_handler))
@@ -0,0 +1,45 @@
+use std::time::Duration;
+use tokio::signal;
+
+#[derive(Debug, Clone)]
+pub struct ShutdownConfig {
+    // Batas waktu menunggu request dan koneksi WebSocket selesai sebelum proses dihentikan paksa.
+    pub timeout: Duration,
+    // Saran jeda sebelum client WebSocket menyambung ulang.
+    pub reconnect_after: Duration,
+}
+
+impl ShutdownConfig {
+    pub fn from_env() -> Self {
+        let env_u64 = |key: &str, default: u64| {
+            std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            timeout: Duration::from_secs(env_u64("SHUTDOWN_TIMEOUT_SECS", 30)),
+            reconnect_after: Duration::from_millis(env_u64("WS_RECONNECT_AFTER_MS", 5000)),
+        }
+    }
+}
+
+// Selesai saat proses menerima SIGINT (Ctrl+C) atau SIGTERM.
+pub async fn wait_for_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c().await.expect("Gagal memasang handler Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Gagal memasang handler SIGTERM")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Menerima SIGINT, memulai shutdown"),
+        _ = terminate => tracing::info!("Menerima SIGTERM, memulai shutdown"),
+    }
+}
@@ -0,0 +1,58 @@
+use sqlx::PgPool;
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct TrashConfig {
+    // Berapa lama baris di tempat sampah masih bisa dipulihkan.
+    pub retention_days: i32,
+    pub purge_interval: Duration,
+}
+
+impl TrashConfig {
+    pub fn from_env() -> Self {
+        let env_or = |key: &str, default: u64| {
+            std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            retention_days: env_or("TRASH_RETENTION_DAYS", 30) as i32,
+            purge_interval: Duration::from_secs(env_or("TRASH_PURGE_INTERVAL_SECS", 3600).max(1)),
+        }
+    }
+}
+
+// Menghapus permanen buku dan email yang sudah lebih lama dari masa retensi di tempat
+// sampah. Event `*Deleted` sudah dikirim saat soft delete, jadi purge tidak menerbitkan event.
+pub fn spawn_purge(pool: PgPool, config: TrashConfig) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.purge_interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = purge(&pool, config.retention_days).await {
+                tracing::warn!("Gagal membersihkan tempat sampah: {}", e);
+            }
+        }
+    });
+}
+
+async fn purge(pool: &PgPool, retention_days: i32) -> Result<(), sqlx::Error> {
+    let books = sqlx::query!(
+        "DELETE FROM books WHERE deleted_at < NOW() - make_interval(days => $1)",
+        retention_days
+    )
+    .execute(pool)
+    .await?;
+    let emails = sqlx::query!(
+        "DELETE FROM emails WHERE deleted_at < NOW() - make_interval(days => $1)",
+        retention_days
+    )
+    .execute(pool)
+    .await?;
+    if books.rows_affected() + emails.rows_affected() > 0 {
+        tracing::info!(
+            "Tempat sampah dibersihkan: {} buku, {} email",
+            books.rows_affected(),
+            emails.rows_affected()
+        );
+    }
+    Ok(())
+}
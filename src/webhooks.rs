@@ -0,0 +1,343 @@
+use crate::{event_bus::SharedEvent, AppState};
+use chrono::Utc;
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast::error::RecvError, Notify};
+use uuid::Uuid;
+
+// Kunci advisory lock agar hanya satu replika yang membuat antrean pengiriman.
+const DISPATCHER_LOCK_KEY: i64 = 0x7773_7300_0001;
+const BATCH_SIZE: i64 = 20;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_BACKOFF_SECS: f64 = 3600.0;
+
+#[derive(Clone)]
+pub struct WebhookConfig {
+    pub max_attempts: i32,
+    pub backoff_base_secs: f64,
+    pub timeout: Duration,
+}
+
+impl WebhookConfig {
+    pub fn from_env() -> Self {
+        let env_or = |key: &str, default: u64| {
+            std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            max_attempts: env_or("WEBHOOK_MAX_ATTEMPTS", 8) as i32,
+            backoff_base_secs: env_or("WEBHOOK_BACKOFF_BASE_SECS", 5) as f64,
+            timeout: Duration::from_secs(env_or("WEBHOOK_TIMEOUT_SECS", 10)),
+        }
+    }
+
+    // Jeda sebelum percobaan berikutnya: base * 2^(percobaan - 1), dibatasi satu jam.
+    fn backoff_secs(&self, attempts: i32) -> f64 {
+        let exp = attempts.saturating_sub(1).min(30);
+        (self.backoff_base_secs * 2f64.powi(exp)).min(MAX_BACKOFF_SECS)
+    }
+
+    // Jeda sebelum dicoba lagi setelah percobaan ke-`attempts` gagal; `None` jika
+    // percobaan sudah habis dan pengiriman ditandai gagal.
+    fn retry_after(&self, attempts: i32) -> Option<f64> {
+        (attempts < self.max_attempts).then(|| self.backoff_secs(attempts))
+    }
+}
+
+// Tanda tangan HMAC-SHA256 atas `"{timestamp}.{body}"`, dikirim sebagai
+// header `X-Webhook-Signature: sha256=<hex>`.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC menerima kunci dengan panjang berapa pun");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+pub fn spawn(state: Arc<AppState>, config: WebhookConfig) {
+    let wake = Arc::new(Notify::new());
+    tokio::spawn(run_dispatcher(state.clone(), wake.clone()));
+    tokio::spawn(run_worker(state.db_pool.clone(), config, wake));
+}
+
+async fn run_dispatcher(state: Arc<AppState>, wake: Arc<Notify>) {
+    loop {
+        // Koneksi ini dipegang selama replika menjadi dispatcher; lock dilepas otomatis
+        // oleh Postgres saat koneksi terputus.
+        let mut conn = match state.db_pool.acquire().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::error!("Dispatcher webhook gagal mendapatkan koneksi: {}", e);
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        };
+        let locked = sqlx::query_scalar!("SELECT pg_try_advisory_lock($1)", DISPATCHER_LOCK_KEY)
+            .fetch_one(&mut *conn)
+            .await;
+        match locked {
+            Ok(Some(true)) => {}
+            Ok(_) => {
+                drop(conn);
+                tokio::time::sleep(POLL_INTERVAL * 2).await;
+                continue;
+            }
+            Err(e) => {
+                tracing::error!("Dispatcher webhook gagal mengambil lock: {}", e);
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+        }
+
+        tracing::info!("Replika ini menjadi dispatcher webhook");
+        let mut rx = state.events.subscribe();
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    match enqueue(&state.db_pool, &event).await {
+                        Ok(0) => {}
+                        Ok(_) => wake.notify_one(),
+                        Err(e) => tracing::error!("Gagal membuat antrean webhook untuk {}: {}", event.value().event.name(), e),
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Dispatcher webhook melewatkan {} event", skipped);
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+}
+
+async fn enqueue(pool: &PgPool, event: &SharedEvent) -> anyhow::Result<u64> {
+    let event_type = event.value().event.name();
+    let payload = serde_json::to_value(event.value())?;
+    let result = sqlx::query!(
+        r#"INSERT INTO webhook_deliveries (webhook_id, event_type, payload)
+           SELECT id, $1, $2 FROM webhooks
+           WHERE active AND (cardinality(events) = 0 OR $1 = ANY(events))"#,
+        event_type,
+        payload
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+struct DueDelivery {
+    id: Uuid,
+    event_type: String,
+    payload: serde_json::Value,
+    attempts: i32,
+    url: String,
+    secret: String,
+}
+
+async fn run_worker(pool: PgPool, config: WebhookConfig, wake: Arc<Notify>) {
+    let client = match reqwest::Client::builder().timeout(config.timeout).build() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Gagal membuat HTTP client webhook: {}", e);
+            return;
+        }
+    };
+    // Lease mencegah replika lain mengambil pengiriman yang sedang diproses.
+    let lease_secs = config.timeout.as_secs_f64() + 30.0;
+
+    loop {
+        let due = sqlx::query_as!(
+            DueDelivery,
+            r#"UPDATE webhook_deliveries d
+               SET next_attempt_at = NOW() + make_interval(secs => $2)
+               FROM webhooks w
+               WHERE d.webhook_id = w.id AND d.id IN (
+                   SELECT id FROM webhook_deliveries
+                   WHERE status = 'pending' AND next_attempt_at <= NOW()
+                   ORDER BY next_attempt_at
+                   LIMIT $1
+                   FOR UPDATE SKIP LOCKED
+               )
+               RETURNING d.id, d.event_type, d.payload, d.attempts, w.url, w.secret"#,
+            BATCH_SIZE,
+            lease_secs
+        )
+        .fetch_all(&pool)
+        .await;
+
+        match due {
+            Ok(due) if !due.is_empty() => {
+                join_all(due.into_iter().map(|d| deliver(&pool, &client, &config, d))).await;
+                continue;
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Gagal mengambil antrean webhook: {}", e),
+        }
+
+        tokio::select! {
+            _ = wake.notified() => {}
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+}
+
+// Hasil satu percobaan HTTP; `error` kosong berarti receiver membalas 2xx.
+struct Attempt {
+    status_code: Option<i32>,
+    error: Option<String>,
+}
+
+async fn deliver(pool: &PgPool, client: &reqwest::Client, config: &WebhookConfig, delivery: DueDelivery) {
+    let attempts = delivery.attempts + 1;
+    let Attempt { status_code, error } = match send(client, &delivery).await {
+        Ok(attempt) => attempt,
+        Err(e) => {
+            tracing::error!("Gagal serialisasi payload webhook {}: {}", delivery.id, e);
+            return;
+        }
+    };
+
+    let result = match error {
+        None => {
+            sqlx::query!(
+                r#"UPDATE webhook_deliveries
+                   SET status = 'succeeded', attempts = $2, last_status_code = $3, last_error = NULL, delivered_at = NOW()
+                   WHERE id = $1"#,
+                delivery.id,
+                attempts,
+                status_code
+            )
+            .execute(pool)
+            .await
+        }
+        Some(error) => {
+            let retry_after = config.retry_after(attempts);
+            tracing::warn!("Pengiriman webhook {} gagal (percobaan {}): {}", delivery.id, attempts, error);
+            sqlx::query!(
+                r#"UPDATE webhook_deliveries
+                   SET status = CASE WHEN $5 THEN 'failed' ELSE 'pending' END,
+                       attempts = $2, last_status_code = $3, last_error = $4,
+                       next_attempt_at = NOW() + make_interval(secs => $6)
+                   WHERE id = $1"#,
+                delivery.id,
+                attempts,
+                status_code,
+                error,
+                retry_after.is_none(),
+                retry_after.unwrap_or_default()
+            )
+            .execute(pool)
+            .await
+        }
+    };
+    if let Err(e) = result {
+        tracing::error!("Gagal mencatat hasil pengiriman webhook {}: {}", delivery.id, e);
+    }
+}
+
+async fn send(client: &reqwest::Client, delivery: &DueDelivery) -> serde_json::Result<Attempt> {
+    let body = serde_json::to_vec(&delivery.payload)?;
+    let timestamp = Utc::now().timestamp();
+    let signature = sign(&delivery.secret, timestamp, &body);
+
+    let response = client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header("X-Webhook-Id", delivery.id.to_string())
+        .header("X-Webhook-Event", &delivery.event_type)
+        .header("X-Webhook-Timestamp", timestamp.to_string())
+        .header("X-Webhook-Signature", format!("sha256={}", signature))
+        .body(body)
+        .send()
+        .await;
+
+    Ok(match response {
+        Ok(res) if res.status().is_success() => Attempt { status_code: Some(res.status().as_u16() as i32), error: None },
+        Ok(res) => Attempt { status_code: Some(res.status().as_u16() as i32), error: Some(format!("HTTP {}", res.status())) },
+        Err(e) => Attempt { status_code: e.status().map(|s| s.as_u16() as i32), error: Some(e.to_string()) },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, extract::State, http::{HeaderMap, StatusCode}, routing::post, Router};
+    use std::sync::Mutex;
+
+    // Receiver lokal yang mencatat setiap request dan membalas status dari `replies` secara berurutan.
+    #[derive(Default)]
+    struct Receiver {
+        replies: Mutex<Vec<StatusCode>>,
+        requests: Mutex<Vec<(HeaderMap, Bytes)>>,
+    }
+
+    async fn receive(State(receiver): State<Arc<Receiver>>, headers: HeaderMap, body: Bytes) -> StatusCode {
+        receiver.requests.lock().unwrap().push((headers, body));
+        receiver.replies.lock().unwrap().remove(0)
+    }
+
+    async fn start_receiver(replies: Vec<StatusCode>) -> (String, Arc<Receiver>) {
+        let receiver = Arc::new(Receiver { replies: Mutex::new(replies), ..Default::default() });
+        let app = Router::new().route("/hook", post(receive)).with_state(receiver.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, receiver)
+    }
+
+    fn due(url: String, attempts: i32) -> DueDelivery {
+        DueDelivery {
+            id: Uuid::new_v4(),
+            event_type: "BookCreated".to_string(),
+            payload: serde_json::json!({ "event": "BookCreated", "data": { "title": "Dune" } }),
+            attempts,
+            url,
+            secret: "rahasia".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn signs_request_and_retries_after_server_error() {
+        let (url, receiver) = start_receiver(vec![StatusCode::SERVICE_UNAVAILABLE, StatusCode::NO_CONTENT]).await;
+        let client = reqwest::Client::new();
+        let config = WebhookConfig { max_attempts: 3, backoff_base_secs: 5.0, timeout: Duration::from_secs(5) };
+
+        let first = send(&client, &due(url.clone(), 0)).await.unwrap();
+        assert_eq!(first.status_code, Some(503));
+        assert!(first.error.is_some());
+        assert_eq!(config.retry_after(1), Some(5.0));
+
+        let second = send(&client, &due(url, 1)).await.unwrap();
+        assert_eq!(second.status_code, Some(204));
+        assert!(second.error.is_none());
+
+        let requests = receiver.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        for (headers, body) in requests.iter() {
+            let header = |name: &str| headers.get(name).unwrap().to_str().unwrap().to_string();
+            let timestamp: i64 = header("x-webhook-timestamp").parse().unwrap();
+            assert_eq!(header("x-webhook-signature"), format!("sha256={}", sign("rahasia", timestamp, body)));
+            assert_eq!(header("x-webhook-event"), "BookCreated");
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_until_capped_and_stops_after_max_attempts() {
+        let config = WebhookConfig { max_attempts: 8, backoff_base_secs: 5.0, timeout: Duration::from_secs(10) };
+        assert_eq!(config.retry_after(1), Some(5.0));
+        assert_eq!(config.retry_after(2), Some(10.0));
+        assert_eq!(config.retry_after(4), Some(40.0));
+        assert_eq!(config.retry_after(8), None);
+
+        let slow = WebhookConfig { max_attempts: 100, ..config };
+        assert_eq!(slow.retry_after(20), Some(MAX_BACKOFF_SECS));
+    }
+
+    #[test]
+    fn signature_matches_known_vector() {
+        // Dihitung dengan `printf '1700000000.{}' | openssl dgst -sha256 -hmac rahasia`.
+        assert_eq!(sign("rahasia", 1_700_000_000, b"{}"), "be2443a5e1a92cad67cb72eee5089a722f140561b03fe2427bfc46ee387d1213");
+    }
+}
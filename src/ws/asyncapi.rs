@@ -0,0 +1,183 @@
+use super::{
+    codec::PROTOCOLS,
+    envelope::{CompactEnvelope, EventEnvelope},
+    message::ServerMessage,
+    subscription::ClientCommand,
+};
+use schemars::{
+    r#gen::{SchemaGenerator, SchemaSettings},
+    schema::{Schema, SchemaObject},
+    JsonSchema,
+};
+use serde_json::{json, Map, Value};
+use std::sync::OnceLock;
+
+const SCHEMAS_PATH: &str = "#/components/schemas/";
+
+// Dokumen AsyncAPI untuk endpoint `/ws`, dibangun dari tipe event dan command yang
+// sama dengan yang diserialisasi server sehingga tidak bisa tertinggal dari kode.
+pub fn document() -> &'static Value {
+    static DOCUMENT: OnceLock<Value> = OnceLock::new();
+    DOCUMENT.get_or_init(build)
+}
+
+fn build() -> Value {
+    let mut generator = SchemaSettings::draft07()
+        .with(|settings| settings.definitions_path = SCHEMAS_PATH.to_string())
+        .into_generator();
+    let envelope = root_schema::<EventEnvelope>(&mut generator);
+    let compact = root_schema::<CompactEnvelope>(&mut generator);
+    let server = root_schema::<ServerMessage>(&mut generator);
+    let client = root_schema::<ClientCommand>(&mut generator);
+
+    let mut messages = Map::new();
+    let mut received = Vec::new();
+    let mut sent = Vec::new();
+
+    // Setiap event yang disiarkan dibungkus `EventEnvelope`.
+    for (name, variant) in variants(&envelope, "event") {
+        let payload = json!({ "allOf": [metadata(&envelope), variant] });
+        received.push(add_message(&mut messages, &name, &name, "Event yang disiarkan", payload));
+    }
+    // Bentuk ringkas event update untuk langganan `"updates": "diff"`.
+    for name in ["BookUpdated", "AuthorUpdated", "EmailUpdated"] {
+        let mut payload = compact.clone();
+        payload["properties"]["event"] = json!({ "type": "string", "enum": [name] });
+        let key = format!("{}Diff", name);
+        received.push(add_message(&mut messages, &key, name, "Event update dengan `\"updates\": \"diff\"`", payload));
+    }
+    for (name, variant) in variants(&server, "event") {
+        received.push(add_message(&mut messages, &name, &name, "Pesan kontrol dari server", variant));
+    }
+    for (name, variant) in variants(&client, "action") {
+        sent.push(add_message(&mut messages, &name, &name, "Command dari client", variant));
+    }
+
+    let schemas: Map<String, Value> = generator
+        .take_definitions()
+        .into_iter()
+        .map(|(name, schema)| (name, serde_json::to_value(schema).unwrap_or_default()))
+        .collect();
+
+    json!({
+        "asyncapi": "2.6.0",
+        "info": {
+            "title": "rust_wss WebSocket API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Event realtime untuk books, emails dan presence. Koneksi memerlukan JWT yang sama dengan REST API."
+        },
+        "defaultContentType": "application/json",
+        "servers": {
+            "default": {
+                "url": "{host}/api/v1",
+                "protocol": "ws",
+                "variables": { "host": { "default": "localhost:8000" } },
+                "description": format!(
+                    "Subprotocol WebSocket menentukan encoding pesan: {}. Tanpa subprotocol dipakai JSON.",
+                    PROTOCOLS.join(", ")
+                )
+            }
+        },
+        "channels": {
+            "/ws": {
+                "bindings": { "ws": { "method": "GET" } },
+                "subscribe": {
+                    "operationId": "receiveMessage",
+                    "summary": "Pesan yang dikirim server ke client",
+                    "message": { "oneOf": received }
+                },
+                "publish": {
+                    "operationId": "sendCommand",
+                    "summary": "Command yang dikirim client ke server",
+                    "message": { "oneOf": sent }
+                }
+            }
+        },
+        "components": {
+            "messages": messages,
+            "schemas": schemas
+        }
+    })
+}
+
+fn root_schema<T: JsonSchema>(generator: &mut SchemaGenerator) -> Value {
+    let schema: SchemaObject = T::json_schema(generator).into();
+    serde_json::to_value(Schema::Object(schema)).unwrap_or_default()
+}
+
+// Varian enum bertag beserta nilai tag-nya, misalnya `("BookCreated", {...})`.
+fn variants(schema: &Value, tag: &str) -> Vec<(String, Value)> {
+    let Some(variants) = schema.get("oneOf").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    variants
+        .iter()
+        .filter_map(|variant| {
+            let name = variant["properties"][tag]["enum"][0].as_str()?;
+            Some((name.to_string(), strip_titles(variant.clone())))
+        })
+        .collect()
+}
+
+// Field envelope selain varian event yang di-flatten.
+fn metadata(envelope: &Value) -> Value {
+    let mut metadata = envelope.clone();
+    if let Some(object) = metadata.as_object_mut() {
+        object.remove("oneOf");
+        object.remove("title");
+    }
+    metadata
+}
+
+fn strip_titles(mut schema: Value) -> Value {
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("title");
+    }
+    schema
+}
+
+fn add_message(messages: &mut Map<String, Value>, key: &str, name: &str, summary: &str, payload: Value) -> Value {
+    messages.insert(
+        key.to_string(),
+        json!({ "name": name, "title": key, "summary": summary, "payload": payload }),
+    );
+    json!({ "$ref": format!("#/components/messages/{}", key) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ws::WsEvent;
+
+    const DOCUMENT_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/docs/asyncapi.json");
+
+    // Dokumen di `docs/asyncapi.json` harus sama dengan yang dihasilkan dari tipe.
+    // Jalankan `UPDATE_ASYNCAPI=1 cargo test asyncapi` untuk memperbaruinya.
+    #[test]
+    fn committed_document_matches_types() {
+        let generated = serde_json::to_string_pretty(document()).unwrap() + "\n";
+        if std::env::var_os("UPDATE_ASYNCAPI").is_some() {
+            std::fs::write(DOCUMENT_PATH, &generated).unwrap();
+            return;
+        }
+        let committed = std::fs::read_to_string(DOCUMENT_PATH).unwrap_or_default();
+        assert!(
+            committed == generated,
+            "docs/asyncapi.json tidak sesuai dengan tipe event; jalankan `UPDATE_ASYNCAPI=1 cargo test asyncapi`"
+        );
+    }
+
+    #[test]
+    fn every_event_has_a_message() {
+        let messages = document()["components"]["messages"].as_object().unwrap();
+        for name in WsEvent::NAMES {
+            assert!(messages.contains_key(*name), "event {} tidak ada di dokumen AsyncAPI", name);
+        }
+        for name in ["CONNECTED", "SUBSCRIBED", "UNSUBSCRIBED", "ERROR", "SNAPSHOT", "SNAPSHOT_END", "TOKEN_EXPIRING", "REAUTHENTICATED", "EVENTS_MISSED", "SERVER_SHUTDOWN"] {
+            assert!(messages.contains_key(name), "pesan {} tidak ada di dokumen AsyncAPI", name);
+        }
+        for name in ["subscribe", "unsubscribe", "reauthenticate"] {
+            assert!(messages.contains_key(name), "command {} tidak ada di dokumen AsyncAPI", name);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use axum::{
+    body::Bytes,
+    extract::ws::{Message, Utf8Bytes},
+    http::HeaderValue,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::OnceLock;
+
+// Subprotocol yang didukung, urutan menentukan prioritas saat negosiasi.
+pub const PROTOCOLS: [&str; 3] = ["json", "msgpack", "cbor"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    MessagePack,
+    Cbor,
+}
+
+impl Encoding {
+    // Tanpa subprotocol yang cocok, client tetap menerima JSON seperti sebelumnya.
+    pub fn from_protocol(protocol: Option<&HeaderValue>) -> Self {
+        match protocol.and_then(|p| p.to_str().ok()) {
+            Some("msgpack") => Encoding::MessagePack,
+            Some("cbor") => Encoding::Cbor,
+            _ => Encoding::Json,
+        }
+    }
+
+    pub fn encode<T: Serialize>(self, value: &T) -> anyhow::Result<Message> {
+        match self {
+            Encoding::Json => Ok(Message::Text(serde_json::to_string(value)?.into())),
+            Encoding::MessagePack => Ok(Message::Binary(rmp_serde::to_vec_named(value)?.into())),
+            Encoding::Cbor => {
+                let mut buf = Vec::new();
+                ciborium::into_writer(value, &mut buf)?;
+                Ok(Message::Binary(buf.into()))
+            }
+        }
+    }
+
+    // Pesan client boleh berupa teks JSON kapan pun; frame biner didekode
+    // sesuai encoding yang dinegosiasikan.
+    pub fn decode<T: DeserializeOwned>(self, message: &Message) -> anyhow::Result<T> {
+        match (message, self) {
+            (Message::Text(text), _) => Ok(serde_json::from_str(text.as_str())?),
+            (Message::Binary(bytes), Encoding::MessagePack) => Ok(rmp_serde::from_slice(bytes)?),
+            (Message::Binary(bytes), Encoding::Cbor) => Ok(ciborium::from_reader(bytes.as_ref())?),
+            (Message::Binary(bytes), Encoding::Json) => Ok(serde_json::from_slice(bytes)?),
+            _ => anyhow::bail!("jenis frame tidak didukung"),
+        }
+    }
+}
+
+// Event yang disiarkan bersama cache hasil serialisasinya. Setiap encoding
+// hanya diserialisasi sekali, oleh koneksi pertama yang membutuhkannya.
+pub struct EncodedEvent<T> {
+    value: T,
+    json: OnceLock<Option<Utf8Bytes>>,
+    msgpack: OnceLock<Option<Bytes>>,
+    cbor: OnceLock<Option<Bytes>>,
+    compact: OnceLock<Option<Box<EncodedEvent<serde_json::Value>>>>,
+}
+
+impl<T: Serialize> EncodedEvent<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            json: OnceLock::new(),
+            msgpack: OnceLock::new(),
+            cbor: OnceLock::new(),
+            compact: OnceLock::new(),
+        }
+    }
+
+    // Bentuk alternatif event (misalnya hanya diff) yang juga diserialisasi sekali per
+    // encoding untuk semua koneksi. `build` hanya dipanggil pada pemanggilan pertama.
+    pub fn compact(&self, build: impl FnOnce(&T) -> Option<serde_json::Value>) -> Option<&EncodedEvent<serde_json::Value>> {
+        self.compact
+            .get_or_init(|| build(&self.value).map(|value| Box::new(EncodedEvent::new(value))))
+            .as_deref()
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn json(&self) -> Option<Utf8Bytes> {
+        self.json
+            .get_or_init(|| match self.encode(Encoding::Json)? {
+                Message::Text(text) => Some(text),
+                _ => None,
+            })
+            .clone()
+    }
+
+    pub fn message(&self, encoding: Encoding) -> Option<Message> {
+        match encoding {
+            Encoding::Json => self.json().map(Message::Text),
+            Encoding::MessagePack => self.binary(&self.msgpack, encoding),
+            Encoding::Cbor => self.binary(&self.cbor, encoding),
+        }
+    }
+
+    fn binary(&self, cell: &OnceLock<Option<Bytes>>, encoding: Encoding) -> Option<Message> {
+        cell.get_or_init(|| match self.encode(encoding)? {
+            Message::Binary(bytes) => Some(bytes),
+            _ => None,
+        })
+        .clone()
+        .map(Message::Binary)
+    }
+
+    fn encode(&self, encoding: Encoding) -> Option<Message> {
+        encoding
+            .encode(&self.value)
+            .map_err(|e| tracing::error!("Gagal serialisasi event WebSocket ({:?}): {}", encoding, e))
+            .ok()
+    }
+}
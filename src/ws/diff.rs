@@ -0,0 +1,32 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FieldChange {
+    pub old: Value,
+    pub new: Value,
+}
+
+// Field yang berubah pada event update, dengan nama field sama seperti di payload
+// (camelCase), misalnya `{"title": {"old": "A", "new": "B"}}`.
+pub type Changes = BTreeMap<String, FieldChange>;
+
+// Membandingkan dua versi record per field top-level setelah diserialisasi.
+pub fn diff<T: Serialize>(old: &T, new: &T) -> Changes {
+    let (Ok(Value::Object(old)), Ok(Value::Object(mut new))) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Changes::new();
+    };
+    let mut changes = Changes::new();
+    for (field, old) in old {
+        let new = new.remove(&field).unwrap_or(Value::Null);
+        if old != new {
+            changes.insert(field, FieldChange { old, new });
+        }
+    }
+    for (field, new) in new {
+        changes.insert(field, FieldChange { old: Value::Null, new });
+    }
+    changes
+}
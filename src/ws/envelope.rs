@@ -0,0 +1,123 @@
+use super::{diff::Changes, WsEvent};
+use crate::auth::Claims;
+use axum::{extract::FromRequestParts, http::request::Parts};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use uuid::Uuid;
+
+// Naikkan setiap kali bentuk envelope atau payload event berubah secara tidak kompatibel.
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Pembungkus setiap event yang disiarkan. Field `event` dan `data` tetap berada di
+// level teratas sehingga client lama yang hanya membaca keduanya tidak terpengaruh:
+// `{"id", "timestamp", "actor", "schemaVersion", "correlationId", "event", "data", "changes"}`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EventEnvelope {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub actor: Option<String>,
+    pub schema_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    #[serde(flatten)]
+    pub event: WsEvent,
+    // Hanya untuk event update: field yang berubah beserta nilai lama dan barunya.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<Changes>,
+}
+
+impl EventEnvelope {
+    pub fn with_changes(mut self, changes: Changes) -> Self {
+        self.changes = Some(changes);
+        self
+    }
+
+    // Bentuk ringkas untuk langganan dengan `"updates": "diff"`: `data` hanya berisi id
+    // record, sedangkan isinya diwakili `changes`. `None` untuk event selain update.
+    pub fn diff_only(&self) -> Option<serde_json::Value> {
+        let id = match &self.event {
+            WsEvent::BookUpdated(book) => book.id,
+            WsEvent::AuthorUpdated(author) => author.id,
+            WsEvent::EmailUpdated(email) => email.id,
+            _ => return None,
+        };
+        let compact = CompactEnvelope {
+            id: self.id,
+            timestamp: self.timestamp,
+            actor: self.actor.clone(),
+            schema_version: self.schema_version,
+            correlation_id: self.correlation_id.clone(),
+            event: self.event.name().to_string(),
+            data: RecordRef { id },
+            changes: self.changes.clone()?,
+        };
+        serde_json::to_value(compact).ok()
+    }
+}
+
+// Bentuk event update untuk langganan `"updates": "diff"`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactEnvelope {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub actor: Option<String>,
+    pub schema_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    pub event: String,
+    pub data: RecordRef,
+    pub changes: Changes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RecordRef {
+    pub id: Uuid,
+}
+
+// Informasi tentang request yang memicu event: siapa pelakunya dan id korelasinya.
+#[derive(Debug, Clone, Default)]
+pub struct EventContext {
+    pub actor: Option<String>,
+    pub correlation_id: Option<String>,
+}
+
+impl EventContext {
+    pub fn for_user(user_id: &str) -> Self {
+        Self { actor: Some(user_id.to_string()), correlation_id: None }
+    }
+
+    pub fn envelope(&self, event: WsEvent) -> EventEnvelope {
+        EventEnvelope {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            actor: self.actor.clone(),
+            schema_version: SCHEMA_VERSION,
+            correlation_id: self.correlation_id.clone(),
+            event,
+            changes: None,
+        }
+    }
+}
+
+// Mengambil pelaku dari `Claims` yang dipasang `auth_middleware` dan id korelasi dari
+// header `X-Correlation-ID` (atau `X-Request-ID`).
+impl<S> FromRequestParts<S> for EventContext
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let actor = parts.extensions.get::<Claims>().map(|claims| claims.sub.clone());
+        let correlation_id = ["x-correlation-id", "x-request-id"]
+            .iter()
+            .find_map(|name| parts.headers.get(*name))
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.chars().take(128).collect());
+        Ok(Self { actor, correlation_id })
+    }
+}
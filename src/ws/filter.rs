@@ -0,0 +1,523 @@
+use crate::models::{author::Author, book::Book, email::Email};
+use chrono::{DateTime, Utc};
+use std::borrow::Cow;
+use std::fmt;
+
+// Ekspresi filter sederhana yang dievaluasi di server sebelum event dikirim, misalnya:
+//
+//   author == "Tolkien" && publicationYear >= 1950 && publicationYear <= 1960
+//   recipient == "ops@example.com" || subject ~ "urgent"
+//
+// Operator: == != < <= > >= dan ~ (mengandung, tidak peka huruf besar/kecil),
+// digabung dengan && || ! dan tanda kurung. Nama field boleh camelCase atau snake_case.
+// Nilai harus sesuai tipe field: string untuk teks, angka untuk angka, dan string
+// RFC 3339 untuk waktu.
+
+// Batas panjang dan kedalaman agar parser rekursif tidak menghabiskan stack.
+pub const MAX_FILTER_LENGTH: usize = 1024;
+pub const MAX_FILTER_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Str(String),
+    Num(f64),
+    Time(DateTime<Utc>),
+    Null,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FieldType {
+    Str,
+    Num,
+    Time,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone)]
+pub enum Filter {
+    Compare { field: &'static str, op: Op, value: Literal },
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (posisi {})", self.message, self.position)
+    }
+}
+
+// Nilai field dari sebuah record saat filter dievaluasi.
+pub enum FieldValue<'a> {
+    Str(Cow<'a, str>),
+    Num(f64),
+    // Dibandingkan dengan literal waktu yang sudah di-parse saat filter dibuat.
+    Time(DateTime<Utc>),
+    Null,
+}
+
+// Record yang bisa difilter. `FIELDS` berisi nama field (camelCase) yang boleh dipakai
+// beserta tipenya.
+pub trait Filterable {
+    const FIELDS: &'static [(&'static str, FieldType)];
+    fn field(&self, name: &str) -> FieldValue<'_>;
+}
+
+impl Filterable for Book {
+    const FIELDS: &'static [(&'static str, FieldType)] = &[
+        ("id", FieldType::Str),
+        ("title", FieldType::Str),
+        ("author", FieldType::Str),
+        ("publicationYear", FieldType::Num),
+        ("isbn", FieldType::Str),
+        ("createdAt", FieldType::Time),
+        ("updatedAt", FieldType::Time),
+    ];
+
+    fn field(&self, name: &str) -> FieldValue<'_> {
+        match name {
+            "id" => FieldValue::Str(self.id.to_string().into()),
+            "title" => FieldValue::Str(self.title.as_str().into()),
+            "author" => FieldValue::Str(self.author.as_str().into()),
+            "publicationYear" => self.publication_year.map_or(FieldValue::Null, |y| FieldValue::Num(y as f64)),
+            "isbn" => self.isbn.as_deref().map_or(FieldValue::Null, |isbn| FieldValue::Str(isbn.into())),
+            "createdAt" => FieldValue::Time(self.created_at),
+            "updatedAt" => FieldValue::Time(self.updated_at),
+            _ => FieldValue::Null,
+        }
+    }
+}
+
+impl Filterable for Author {
+    const FIELDS: &'static [(&'static str, FieldType)] = &[
+        ("id", FieldType::Str),
+        ("name", FieldType::Str),
+        ("bio", FieldType::Str),
+        ("createdAt", FieldType::Time),
+        ("updatedAt", FieldType::Time),
+    ];
+
+    fn field(&self, name: &str) -> FieldValue<'_> {
+        match name {
+            "id" => FieldValue::Str(self.id.to_string().into()),
+            "name" => FieldValue::Str(self.name.as_str().into()),
+            "bio" => self.bio.as_deref().map_or(FieldValue::Null, |b| FieldValue::Str(b.into())),
+            "createdAt" => FieldValue::Time(self.created_at),
+            "updatedAt" => FieldValue::Time(self.updated_at),
+            _ => FieldValue::Null,
+        }
+    }
+}
+
+impl Filterable for Email {
+    const FIELDS: &'static [(&'static str, FieldType)] = &[
+        ("id", FieldType::Str),
+        ("sender", FieldType::Str),
+        ("recipient", FieldType::Str),
+        ("subject", FieldType::Str),
+        ("body", FieldType::Str),
+        ("sentAt", FieldType::Time),
+    ];
+
+    fn field(&self, name: &str) -> FieldValue<'_> {
+        match name {
+            "id" => FieldValue::Str(self.id.to_string().into()),
+            "sender" => FieldValue::Str(self.sender.as_str().into()),
+            "recipient" => FieldValue::Str(self.recipient.as_str().into()),
+            "subject" => FieldValue::Str(self.subject.as_str().into()),
+            "body" => self.body.as_deref().map_or(FieldValue::Null, |b| FieldValue::Str(b.into())),
+            "sentAt" => FieldValue::Time(self.sent_at),
+            _ => FieldValue::Null,
+        }
+    }
+}
+
+impl Filter {
+    pub fn parse(input: &str, fields: &'static [(&'static str, FieldType)]) -> Result<Filter, FilterError> {
+        if input.len() > MAX_FILTER_LENGTH {
+            return Err(FilterError {
+                message: format!("filter terlalu panjang (maksimum {} byte)", MAX_FILTER_LENGTH),
+                position: MAX_FILTER_LENGTH,
+            });
+        }
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0, fields, end: input.len(), depth: 0 };
+        let filter = parser.parse_or()?;
+        if let Some((_, position)) = parser.tokens.get(parser.pos) {
+            return Err(FilterError { message: "token tidak terduga".to_string(), position: *position });
+        }
+        Ok(filter)
+    }
+
+    pub fn matches<R: Filterable>(&self, record: &R) -> bool {
+        match self {
+            Filter::And(a, b) => a.matches(record) && b.matches(record),
+            Filter::Or(a, b) => a.matches(record) || b.matches(record),
+            Filter::Not(inner) => !inner.matches(record),
+            Filter::Compare { field, op, value } => compare(&record.field(field), *op, value),
+        }
+    }
+}
+
+fn compare(actual: &FieldValue<'_>, op: Op, expected: &Literal) -> bool {
+    match (actual, expected) {
+        (FieldValue::Null, Literal::Null) => matches!(op, Op::Eq),
+        (_, Literal::Null) => matches!(op, Op::Ne),
+        (FieldValue::Str(a), Literal::Str(b)) => match op {
+            Op::Contains => a.to_lowercase().contains(&b.to_lowercase()),
+            _ => ordering_matches(op, a.as_ref().cmp(b.as_str())),
+        },
+        (FieldValue::Time(a), Literal::Time(b)) => ordering_matches(op, a.cmp(b)),
+        (FieldValue::Num(a), Literal::Num(b)) => match a.partial_cmp(b) {
+            Some(ordering) if op != Op::Contains => ordering_matches(op, ordering),
+            _ => false,
+        },
+        _ => matches!(op, Op::Ne),
+    }
+}
+
+fn ordering_matches(op: Op, ordering: std::cmp::Ordering) -> bool {
+    use std::cmp::Ordering::*;
+    match op {
+        Op::Eq => ordering == Equal,
+        Op::Ne => ordering != Equal,
+        Op::Lt => ordering == Less,
+        Op::Le => ordering != Greater,
+        Op::Gt => ordering == Greater,
+        Op::Ge => ordering != Less,
+        Op::Contains => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, FilterError> {
+    let chars: Vec<(usize, char)> = input.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let err = |message: &str, position: usize| FilterError { message: message.to_string(), position };
+
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => { tokens.push((Token::LParen, pos)); i += 1; }
+            ')' => { tokens.push((Token::RParen, pos)); i += 1; }
+            '~' => { tokens.push((Token::Op(Op::Contains), pos)); i += 1; }
+            '&' if next == Some('&') => { tokens.push((Token::And, pos)); i += 2; }
+            '|' if next == Some('|') => { tokens.push((Token::Or, pos)); i += 2; }
+            '=' if next == Some('=') => { tokens.push((Token::Op(Op::Eq), pos)); i += 2; }
+            '!' if next == Some('=') => { tokens.push((Token::Op(Op::Ne), pos)); i += 2; }
+            '!' => { tokens.push((Token::Not, pos)); i += 1; }
+            '<' if next == Some('=') => { tokens.push((Token::Op(Op::Le), pos)); i += 2; }
+            '<' => { tokens.push((Token::Op(Op::Lt), pos)); i += 1; }
+            '>' if next == Some('=') => { tokens.push((Token::Op(Op::Ge), pos)); i += 2; }
+            '>' => { tokens.push((Token::Op(Op::Gt), pos)); i += 1; }
+            '"' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(err("string tidak ditutup", pos)),
+                        Some((_, '"')) => { i += 1; break; }
+                        Some((_, '\\')) => {
+                            match chars.get(i + 1) {
+                                Some((_, escaped)) => value.push(*escaped),
+                                None => return Err(err("string tidak ditutup", pos)),
+                            }
+                            i += 2;
+                        }
+                        Some((_, ch)) => { value.push(*ch); i += 1; }
+                    }
+                }
+                tokens.push((Token::Str(value), pos));
+            }
+            c if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                let start = i;
+                i += 1;
+                while chars.get(i).is_some_and(|(_, ch)| ch.is_ascii_digit() || *ch == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().map(|(_, ch)| ch).collect();
+                let value = text.parse().map_err(|_| err("angka tidak valid", pos))?;
+                tokens.push((Token::Num(value), pos));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while chars.get(i).is_some_and(|(_, ch)| ch.is_alphanumeric() || *ch == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().map(|(_, ch)| ch).collect();
+                tokens.push((Token::Ident(word), pos));
+            }
+            _ => return Err(err(&format!("karakter tidak dikenal '{}'", c), pos)),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    fields: &'static [(&'static str, FieldType)],
+    end: usize,
+    // Jumlah `!` dan `(` yang sedang dibuka.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end, |(_, p)| *p)
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, FilterError> {
+        Err(FilterError { message: message.to_string(), position: self.position() })
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, FilterError> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            left = Filter::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, FilterError> {
+        let mut left = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            left = Filter::And(Box::new(left), Box::new(self.parse_unary()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter, FilterError> {
+        if !matches!(self.peek(), Some(Token::Not | Token::LParen)) {
+            return self.parse_comparison();
+        }
+        if self.depth == MAX_FILTER_DEPTH {
+            return self.error(&format!("filter terlalu dalam (maksimum {} tingkat)", MAX_FILTER_DEPTH));
+        }
+        self.depth += 1;
+        let filter = if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            Filter::Not(Box::new(self.parse_unary()?))
+        } else {
+            self.pos += 1;
+            let inner = self.parse_or()?;
+            if self.peek() != Some(&Token::RParen) {
+                return self.error("diharapkan ')'");
+            }
+            self.pos += 1;
+            inner
+        };
+        self.depth -= 1;
+        Ok(filter)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Filter, FilterError> {
+        let (field, kind) = match self.peek() {
+            Some(Token::Ident(name)) => {
+                let normalized = to_camel_case(name);
+                match self.fields.iter().find(|(f, _)| *f == normalized) {
+                    Some(field) => *field,
+                    None => {
+                        let names: Vec<&str> = self.fields.iter().map(|(f, _)| *f).collect();
+                        return self.error(&format!(
+                            "field '{}' tidak dikenal, gunakan salah satu dari: {}",
+                            name,
+                            names.join(", ")
+                        ));
+                    }
+                }
+            }
+            _ => return self.error("diharapkan nama field"),
+        };
+        self.pos += 1;
+
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            _ => return self.error("diharapkan operator perbandingan"),
+        };
+        self.pos += 1;
+
+        let value = match self.peek() {
+            Some(Token::Str(s)) => Literal::Str(s.clone()),
+            Some(Token::Num(n)) => Literal::Num(*n),
+            Some(Token::Ident(word)) if word == "null" => Literal::Null,
+            _ => return self.error("diharapkan nilai (string, angka atau null)"),
+        };
+        if op == Op::Contains && (kind != FieldType::Str || !matches!(value, Literal::Str(_))) {
+            return self.error("operator '~' hanya untuk field teks dengan nilai string");
+        }
+        if matches!(value, Literal::Null) && !matches!(op, Op::Eq | Op::Ne) {
+            return self.error("null hanya bisa dibandingkan dengan == atau !=");
+        }
+        // Nilai yang tipenya tidak cocok tidak akan pernah cocok dengan record mana pun,
+        // jadi ditolak di sini alih-alih diam-diam tidak mengirim apa-apa.
+        let value = match (kind, value) {
+            (_, Literal::Null) => Literal::Null,
+            (FieldType::Str, value @ Literal::Str(_)) | (FieldType::Num, value @ Literal::Num(_)) => value,
+            (FieldType::Time, Literal::Str(text)) => match DateTime::parse_from_rfc3339(&text) {
+                Ok(time) => Literal::Time(time.with_timezone(&Utc)),
+                Err(_) => return self.error(&format!("field '{}' berisi waktu; gunakan string RFC 3339", field)),
+            },
+            (FieldType::Str, _) => return self.error(&format!("field '{}' berisi teks; gunakan string", field)),
+            (FieldType::Num, _) => return self.error(&format!("field '{}' berisi angka", field)),
+            (FieldType::Time, _) => return self.error(&format!("field '{}' berisi waktu; gunakan string RFC 3339", field)),
+        };
+        self.pos += 1;
+        Ok(Filter::Compare { field, op, value })
+    }
+}
+
+fn to_camel_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    fn book(author: &str, year: Option<i32>) -> Book {
+        let created_at = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        Book {
+            id: Uuid::nil(),
+            title: "The Hobbit".to_string(),
+            author: author.to_string(),
+            publication_year: year,
+            isbn: None,
+            created_at,
+            updated_at: created_at,
+            version: 1,
+            created_by: None,
+            deleted_at: None,
+        }
+    }
+
+    fn parse(input: &str) -> Result<Filter, FilterError> {
+        Filter::parse(input, Book::FIELDS)
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        // Dibaca sebagai `author == "X" || (publicationYear > 2000 && author ~ "tolk")`.
+        let filter = parse(r#"author == "X" || publicationYear > 2000 && author ~ "tolk""#).unwrap();
+        assert!(filter.matches(&book("X", Some(1937))));
+        assert!(!filter.matches(&book("Tolkien", Some(1937))));
+        assert!(filter.matches(&book("Tolkien", Some(2001))));
+
+        let grouped = parse(r#"(author == "X" || publicationYear > 2000) && author ~ "tolk""#).unwrap();
+        assert!(!grouped.matches(&book("X", Some(1937))));
+        assert!(grouped.matches(&book("Tolkien", Some(2001))));
+    }
+
+    #[test]
+    fn not_applies_to_the_nearest_operand() {
+        let filter = parse(r#"!author == "X" && publication_year == null"#).unwrap();
+        assert!(filter.matches(&book("Tolkien", None)));
+        assert!(!filter.matches(&book("X", None)));
+        assert!(!filter.matches(&book("Tolkien", Some(1937))));
+    }
+
+    #[test]
+    fn compares_times_parsed_up_front() {
+        let filter = parse(r#"createdAt >= "2024-01-01T00:00:00Z" && createdAt < "2024-05-01T12:00:00+00:00""#).unwrap();
+        assert!(!filter.matches(&book("Tolkien", None)));
+        let filter = parse(r#"createdAt == "2024-05-01T14:00:00+02:00""#).unwrap();
+        assert!(filter.matches(&book("Tolkien", None)));
+    }
+
+    #[test]
+    fn rejects_values_of_the_wrong_type() {
+        for input in [
+            r#"publicationYear == "x""#,
+            r#"createdAt >= "notadate""#,
+            r#"createdAt >= 2024"#,
+            r#"title == 1"#,
+            r#"publicationYear ~ "19""#,
+            r#"createdAt ~ "2024""#,
+            r#"author < null"#,
+        ] {
+            assert!(parse(input).is_err(), "{} seharusnya ditolak", input);
+        }
+    }
+
+    #[test]
+    fn reports_position_of_the_bad_value() {
+        let err = parse(r#"author == "X" && publicationYear == "x""#).unwrap_err();
+        assert_eq!(err.position, 36);
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        let nested = |depth: usize| format!("{}author == \"X\"{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse(&nested(MAX_FILTER_DEPTH)).is_ok());
+        let err = parse(&nested(MAX_FILTER_DEPTH + 1)).unwrap_err();
+        assert!(err.message.contains("terlalu dalam"), "{}", err);
+
+        let negated = format!("{}author == \"X\"", "!".repeat(MAX_FILTER_DEPTH + 1));
+        assert!(parse(&negated).is_err());
+    }
+
+    #[test]
+    fn limits_length() {
+        let long = format!("author == \"{}\"", "a".repeat(MAX_FILTER_LENGTH));
+        let err = parse(&long).unwrap_err();
+        assert!(err.message.contains("terlalu panjang"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_trailing_tokens() {
+        assert!(parse(r#"publisher == "X""#).is_err());
+        assert!(parse(r#"author == "X" )"#).is_err());
+        assert!(parse(r#"(author == "X""#).is_err());
+    }
+}
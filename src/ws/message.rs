@@ -0,0 +1,70 @@
+use super::subscription::{CommandError, Topic, UpdateFormat};
+use crate::models::{author::Author, book::Book, email::Email};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// Pesan kontrol dari server ke client, di luar event yang disiarkan (`WsEvent`).
+// Bentuknya sama dengan event: `{"event": "...", "data": ...}`.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "event", content = "data", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ServerMessage {
+    // Dikirim sekali tepat setelah koneksi terbuka.
+    Connected(String),
+    Subscribed(Subscribed),
+    Unsubscribed {
+        topic: Topic,
+    },
+    Error(CommandError),
+    Snapshot(SnapshotPage),
+    SnapshotEnd {
+        topic: Topic,
+        total: usize,
+    },
+    // Token koneksi akan kedaluwarsa; kirim `reauthenticate` sebelum `expiresAt`.
+    #[serde(rename_all = "camelCase")]
+    TokenExpiring {
+        expires_at: DateTime<Utc>,
+        expires_in_secs: i64,
+    },
+    #[serde(rename_all = "camelCase")]
+    Reauthenticated {
+        expires_at: DateTime<Utc>,
+    },
+    // Koneksi tertinggal dari siaran event dan `missed` event terlewat (termasuk event yang
+    // ditahan selama snapshot yang sedang berjalan, yang ikut dibatalkan). Client perlu
+    // mengulang `subscribe` dengan snapshot agar datanya kembali lengkap.
+    EventsMissed {
+        missed: u64,
+    },
+    // Server akan berhenti; client sebaiknya menyambung ulang setelah jeda ini.
+    #[serde(rename_all = "camelCase")]
+    ServerShutdown {
+        reconnect_after_ms: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscribed {
+    pub topic: Topic,
+    pub filter: Option<String>,
+    pub snapshot: bool,
+    pub updates: UpdateFormat,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotPage {
+    pub topic: Topic,
+    pub page: usize,
+    pub items: SnapshotItems,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum SnapshotItems {
+    Books(Vec<Book>),
+    Authors(Vec<Author>),
+    Emails(Vec<Email>),
+}
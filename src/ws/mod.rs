@@ -0,0 +1,484 @@
+use crate::{
+    auth::Claims,
+    error::AppError,
+    event_bus::SharedEvent,
+    models::{
+        author::{Author, BookAuthors},
+        book::Book,
+        bulk::BookImportSummary,
+        email::Email,
+    },
+    outbox,
+    presence::{ConnectionSlot, OfflineUser, OnlineUser},
+    AppState,
+};
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket};
+use futures_util::stream::StreamExt; // MODIFIED: Removed SinkExt
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+pub mod asyncapi;
+pub mod codec;
+pub mod diff;
+pub mod envelope;
+pub mod filter;
+pub mod message;
+pub mod outbound;
+pub mod session;
+pub mod snapshot;
+pub mod subscription;
+
+use codec::Encoding;
+use envelope::{EventContext, EventEnvelope};
+use message::{ServerMessage, Subscribed};
+use outbound::{OutboundQueue, OverflowPolicy};
+use session::{Expiry, TokenSession};
+use snapshot::{SnapshotDone, SnapshotRequest, SnapshotTask};
+use subscription::{ClientCommand, CommandError, Subscriptions, Topic, UpdateFormat};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitPolicy {
+    // Tolak upgrade baru dengan 429.
+    Reject,
+    // Terima koneksi baru dan tutup koneksi tertua milik user yang sama.
+    EvictOldest,
+}
+
+#[derive(Debug, Clone)]
+pub struct WsConfig {
+    pub queue_capacity: usize,
+    pub overflow_policy: OverflowPolicy,
+    pub max_connections: usize,
+    pub max_connections_per_user: usize,
+    pub limit_policy: LimitPolicy,
+    // Jarak peringatan `TOKEN_EXPIRING` sebelum token koneksi kedaluwarsa.
+    pub token_warning: Duration,
+    // Batas waktu satu pengiriman ke socket, termasuk close frame.
+    pub send_timeout: Duration,
+}
+
+impl WsConfig {
+    pub fn from_env() -> Self {
+        let env_usize = |key: &str, default: usize| {
+            std::env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        let overflow_policy = std::env::var("WS_OVERFLOW_POLICY")
+            .ok()
+            .and_then(|v| OverflowPolicy::parse(&v))
+            .unwrap_or(OverflowPolicy::DropOldest);
+        let limit_policy = match std::env::var("WS_LIMIT_POLICY").as_deref() {
+            Ok("evict_oldest") => LimitPolicy::EvictOldest,
+            _ => LimitPolicy::Reject,
+        };
+        Self {
+            queue_capacity: env_usize("WS_QUEUE_CAPACITY", 256),
+            overflow_policy,
+            max_connections: env_usize("WS_MAX_CONNECTIONS", 10_000),
+            max_connections_per_user: env_usize("WS_MAX_CONNECTIONS_PER_USER", 5).max(1),
+            limit_policy,
+            token_warning: Duration::from_secs(env_usize("WS_TOKEN_WARNING_SECS", 60) as u64),
+            send_timeout: Duration::from_secs(env_usize("WS_SEND_TIMEOUT_SECS", 10).max(1) as u64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "event", content = "data")]
+pub enum WsEvent {
+    BookCreated(Book), BookUpdated(Book), BookDeleted(Uuid), BookRestored(Book), BooksImported(BookImportSummary),
+    BookAuthorsUpdated(BookAuthors),
+    AuthorCreated(Author), AuthorUpdated(Author), AuthorDeleted(Uuid),
+    EmailCreated(Email), EmailUpdated(Email), EmailDeleted(Uuid), EmailRestored(Email),
+    UserOnline(OnlineUser), UserOffline(OfflineUser),
+}
+
+impl WsEvent {
+    pub const NAMES: &'static [&'static str] = &[
+        "BookCreated", "BookUpdated", "BookDeleted", "BookRestored", "BooksImported", "BookAuthorsUpdated",
+        "AuthorCreated", "AuthorUpdated", "AuthorDeleted",
+        "EmailCreated", "EmailUpdated", "EmailDeleted", "EmailRestored",
+        "UserOnline", "UserOffline",
+    ];
+
+    // Sama dengan nilai field `event` saat diserialisasi.
+    pub fn name(&self) -> &'static str {
+        match self {
+            WsEvent::BookCreated(_) => "BookCreated",
+            WsEvent::BookUpdated(_) => "BookUpdated",
+            WsEvent::BookDeleted(_) => "BookDeleted",
+            WsEvent::BookRestored(_) => "BookRestored",
+            WsEvent::BooksImported(_) => "BooksImported",
+            WsEvent::BookAuthorsUpdated(_) => "BookAuthorsUpdated",
+            WsEvent::AuthorCreated(_) => "AuthorCreated",
+            WsEvent::AuthorUpdated(_) => "AuthorUpdated",
+            WsEvent::AuthorDeleted(_) => "AuthorDeleted",
+            WsEvent::EmailCreated(_) => "EmailCreated",
+            WsEvent::EmailUpdated(_) => "EmailUpdated",
+            WsEvent::EmailDeleted(_) => "EmailDeleted",
+            WsEvent::EmailRestored(_) => "EmailRestored",
+            WsEvent::UserOnline(_) => "UserOnline",
+            WsEvent::UserOffline(_) => "UserOffline",
+        }
+    }
+
+    pub fn topic(&self) -> Topic {
+        match self {
+            WsEvent::BookCreated(_) | WsEvent::BookUpdated(_) | WsEvent::BookDeleted(_) | WsEvent::BookRestored(_) | WsEvent::BooksImported(_) | WsEvent::BookAuthorsUpdated(_) => Topic::Books,
+            WsEvent::AuthorCreated(_) | WsEvent::AuthorUpdated(_) | WsEvent::AuthorDeleted(_) => Topic::Authors,
+            WsEvent::EmailCreated(_) | WsEvent::EmailUpdated(_) | WsEvent::EmailDeleted(_) | WsEvent::EmailRestored(_) => Topic::Emails,
+            WsEvent::UserOnline(_) | WsEvent::UserOffline(_) => Topic::Presence,
+        }
+    }
+
+    // Event dengan kunci yang sama menggambarkan entitas yang sama, sehingga versi
+    // lama di antrean boleh diganti yang terbaru (kebijakan `coalesce`).
+    pub fn coalesce_key(&self) -> String {
+        match self {
+            WsEvent::BookCreated(book) | WsEvent::BookUpdated(book) | WsEvent::BookRestored(book) => format!("book:{}", book.id),
+            WsEvent::BookDeleted(id) => format!("book:{}", id),
+            WsEvent::BooksImported(summary) => format!("import:{}", summary.import_id),
+            WsEvent::BookAuthorsUpdated(authors) => format!("book-authors:{}", authors.book_id),
+            WsEvent::AuthorCreated(author) | WsEvent::AuthorUpdated(author) => format!("author:{}", author.id),
+            WsEvent::AuthorDeleted(id) => format!("author:{}", id),
+            WsEvent::EmailCreated(email) | WsEvent::EmailUpdated(email) | WsEvent::EmailRestored(email) => format!("email:{}", email.id),
+            WsEvent::EmailDeleted(id) => format!("email:{}", id),
+            WsEvent::UserOnline(user) => format!("user:{}", user.user_id),
+            WsEvent::UserOffline(user) => format!("user:{}", user.user_id),
+        }
+    }
+}
+
+pub fn new_queue(config: &WsConfig) -> Arc<OutboundQueue> {
+    Arc::new(OutboundQueue::new(config.queue_capacity, config.overflow_policy))
+}
+
+pub async fn handle_socket(socket: WebSocket, claims: Claims, slot: ConnectionSlot, queue: Arc<OutboundQueue>, state: Arc<AppState>) {
+    let encoding = Encoding::from_protocol(socket.protocol());
+    let user_id = slot.user_id().to_string();
+    let connection_id = slot.connection_id();
+    tracing::info!("WebSocket client terhubung: {} (koneksi {})", user_id, connection_id);
+    if slot.is_first()
+        && let Some(online) = state.presence.get(&user_id, false)
+    {
+        publish_presence(&state, &user_id, WsEvent::UserOnline(online)).await;
+    }
+
+    run_socket(socket, TokenSession::new(claims), &user_id, connection_id, encoding, queue, &state).await;
+
+    if let Some(offline) = slot.release() {
+        publish_presence(&state, &user_id, WsEvent::UserOffline(offline)).await;
+    }
+    tracing::info!("Koneksi WebSocket untuk {} telah ditutup.", user_id);
+}
+
+// Status presence juga lewat outbox, sehingga setiap event punya baris di `event_outbox`
+// dan ikut urutan serta pemutaran ulang yang sama dengan event data.
+async fn publish_presence(state: &AppState, user_id: &str, event: WsEvent) {
+    let envelope = EventContext::for_user(user_id).envelope(event);
+    let result = async {
+        let mut tx = state.db_pool.begin().await?;
+        outbox::enqueue(&mut tx, &envelope).await?;
+        tx.commit().await?;
+        Ok::<_, AppError>(())
+    }
+    .await;
+    match result {
+        Ok(()) => state.outbox.wake(),
+        Err(e) => tracing::error!("Gagal menerbitkan status presence {}: {:?}", user_id, e),
+    }
+}
+
+// Snapshot yang sedang dikirim. Event live untuk topiknya ditahan sampai snapshot
+// selesai agar tidak mendahului `SNAPSHOT_END`.
+struct PendingSnapshot {
+    topic: Topic,
+    task: SnapshotTask,
+    buffered: Vec<SharedEvent>,
+}
+
+async fn wait_snapshot(pending: &mut Option<PendingSnapshot>) -> anyhow::Result<SnapshotDone> {
+    match pending {
+        Some(pending) => pending.task.join().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn run_socket(
+    socket: WebSocket,
+    mut session: TokenSession,
+    user_id: &str,
+    connection_id: Uuid,
+    encoding: Encoding,
+    queue: Arc<OutboundQueue>,
+    state: &AppState,
+) {
+    let mut rx = state.events.subscribe();
+    let mut shutdown = state.shutdown.clone();
+    let mut subscriptions = Subscriptions::default();
+    let mut pending: Option<PendingSnapshot> = None;
+    let (sink, mut stream) = socket.split();
+    let send_timeout = state.ws_config.send_timeout;
+    let mut writer = tokio::spawn(outbound::write_loop(sink, queue.clone(), send_timeout));
+
+    let welcome_msg = ServerMessage::Connected(format!(
+        "Welcome, user {}! You are now listening for updates.",
+        user_id
+    ));
+    if let Ok(welcome_msg) = encoding.encode(&welcome_msg) {
+        queue.push(welcome_msg, None);
+    }
+
+    loop {
+        tokio::select! {
+            // Menerima pesan baru dari channel broadcast dan memasukkannya ke antrean keluar
+            received = rx.recv() => {
+                let event = match received {
+                    Ok(event) => event,
+                    // Channel broadcast sudah menimpa event yang belum dibaca koneksi ini.
+                    Err(RecvError::Lagged(missed)) => {
+                        if !on_lagged(missed, encoding, &queue, &mut pending, state).await {
+                            tracing::warn!("Client {} tertinggal {} event, koneksi {} ditutup", user_id, missed, connection_id);
+                            break;
+                        }
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if !subscriptions.matches(&event.value().event) {
+                    continue;
+                }
+                if let Some(pending) = pending.as_mut().filter(|p| p.topic == event.value().event.topic()) {
+                    pending.buffered.push(event);
+                    continue;
+                }
+                let Some(msg) = event_message(&event, encoding, &subscriptions) else {
+                    continue;
+                };
+                if !queue.push(msg, Some(event.value().event.coalesce_key())) {
+                    tracing::warn!("Client {} terlalu lambat, koneksi {} ditutup", user_id, connection_id);
+                    break;
+                }
+            }
+            // Menerima pesan dari client (misalnya, ping atau close)
+            Some(Ok(msg)) = stream.next() => {
+                state.presence.touch(user_id, connection_id);
+                match msg {
+                    // Client meminta untuk menutup koneksi
+                    Message::Close(_) => break,
+                    Message::Text(_) | Message::Binary(_) => {
+                        let (reply, request) = handle_command(&msg, encoding, &mut subscriptions, &mut pending, &mut session);
+                        if let Ok(reply) = encoding.encode(&reply) {
+                            queue.push(reply, None);
+                        }
+                        if let Some(request) = request {
+                            pending = Some(PendingSnapshot {
+                                topic: request.topic,
+                                task: SnapshotTask::spawn(state.db_pool.clone(), queue.clone(), encoding, request),
+                                buffered: Vec::new(),
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            // Snapshot selesai: kirim penanda lalu event yang ditahan selama snapshot
+            result = wait_snapshot(&mut pending) => {
+                let Some(PendingSnapshot { topic, buffered, .. }) = pending.take() else {
+                    continue;
+                };
+                if !finish_snapshot(result, topic, buffered, encoding, &queue, &subscriptions, state).await {
+                    tracing::warn!("Client {} terlalu lambat, koneksi {} ditutup", user_id, connection_id);
+                    break;
+                }
+            }
+            // Token mendekati atau melewati `exp`: peringatkan dulu, lalu tutup dengan 1008
+            _ = tokio::time::sleep_until(session.deadline(state.ws_config.token_warning)) => {
+                match session.on_deadline() {
+                    Expiry::Warn(expires_at) => {
+                        let notice = ServerMessage::TokenExpiring {
+                            expires_at,
+                            expires_in_secs: (expires_at - chrono::Utc::now()).num_seconds().max(0),
+                        };
+                        if let Ok(msg) = encoding.encode(&notice) {
+                            queue.push(msg, None);
+                        }
+                    }
+                    Expiry::Expired => {
+                        tracing::info!("Token {} kedaluwarsa, koneksi {} ditutup", user_id, connection_id);
+                        queue.finish(Some(CloseFrame {
+                            code: close_code::POLICY,
+                            reason: "token expired".into(),
+                        }));
+                        break;
+                    }
+                }
+            }
+            // Server akan berhenti: kirim petunjuk reconnect lalu close frame "going away"
+            _ = shutdown.changed() => {
+                let reconnect_after_ms = state.shutdown_config.reconnect_after.as_millis() as u64;
+                let notice = ServerMessage::ServerShutdown { reconnect_after_ms };
+                if let Ok(msg) = encoding.encode(&notice) {
+                    queue.push(msg, None);
+                }
+                queue.finish(Some(CloseFrame {
+                    code: close_code::AWAY,
+                    reason: format!("server shutting down; reconnect after {}ms", reconnect_after_ms).into(),
+                }));
+                break;
+            }
+            // Writer berhenti karena client terputus
+            _ = &mut writer => {
+                return;
+            }
+            // Kedua channel ditutup, keluar dari loop
+            else => {
+                break;
+            }
+        }
+    }
+
+    // Beri writer kesempatan mengirim close frame sebelum socket dilepas, tetapi jangan
+    // menunggu selamanya: koneksi (dan slot batas koneksinya) harus selalu dilepas.
+    queue.close(None);
+    if tokio::time::timeout(send_timeout, &mut writer).await.is_err() {
+        writer.abort();
+    }
+}
+
+// Koneksi tertinggal lebih dari kapasitas channel broadcast. Kebijakan `disconnect`
+// menutup koneksi seperti antrean penuh; kebijakan lain memberi tahu client lewat
+// `EVENTS_MISSED` agar berlangganan ulang. Snapshot yang sedang berjalan dibatalkan karena
+// event yang ditahannya sudah tidak lengkap. Mengembalikan false jika koneksi harus ditutup.
+async fn on_lagged(missed: u64, encoding: Encoding, queue: &OutboundQueue, pending: &mut Option<PendingSnapshot>, state: &AppState) -> bool {
+    if state.ws_config.overflow_policy == OverflowPolicy::Disconnect {
+        queue.close(Some(CloseFrame {
+            code: outbound::CLOSE_SLOW_CONSUMER,
+            reason: "slow consumer".into(),
+        }));
+        return false;
+    }
+    *pending = None;
+    match encoding.encode(&ServerMessage::EventsMissed { missed }) {
+        Ok(msg) => queue.push_wait(msg).await,
+        Err(_) => true,
+    }
+}
+
+// Mengirim `SNAPSHOT_END` lalu event yang ditahan selama snapshot. Event yang sudah
+// tercermin di data snapshot dibuang, sehingga tidak ada celah maupun duplikat.
+// Mengembalikan false jika koneksi harus ditutup karena antrean penuh.
+async fn finish_snapshot(
+    result: anyhow::Result<SnapshotDone>,
+    topic: Topic,
+    buffered: Vec<SharedEvent>,
+    encoding: Encoding,
+    queue: &OutboundQueue,
+    subscriptions: &Subscriptions,
+    state: &AppState,
+) -> bool {
+    let (marker, reflected) = match result {
+        Ok(done) => {
+            let ids: Vec<Uuid> = buffered.iter().map(|event| event.value().id).collect();
+            match snapshot::reflected_events(&state.db_pool, &done.pg_snapshot, &ids).await {
+                Ok(reflected) => (ServerMessage::SnapshotEnd { topic: done.topic, total: done.total }, reflected),
+                Err(e) => {
+                    tracing::error!("Gagal memeriksa event snapshot: {:?}", e);
+                    (snapshot_error(topic), Default::default())
+                }
+            }
+        }
+        Err(e) => {
+            tracing::error!("Snapshot {:?} gagal: {:?}", topic, e);
+            (snapshot_error(topic), Default::default())
+        }
+    };
+    if let Ok(msg) = encoding.encode(&marker)
+        && !queue.push_wait(msg).await
+    {
+        return false;
+    }
+    for event in buffered {
+        if reflected.contains(&event.value().id) || !subscriptions.matches(&event.value().event) {
+            continue;
+        }
+        let Some(msg) = event_message(&event, encoding, subscriptions) else {
+            continue;
+        };
+        // Event yang ditahan selama snapshot harus sampai agar client tidak tertinggal
+        // dari snapshot yang baru diterimanya.
+        if !queue.push_wait(msg).await {
+            return false;
+        }
+    }
+    true
+}
+
+// Memilih bentuk event sesuai format update langganan topiknya.
+fn event_message(event: &SharedEvent, encoding: Encoding, subscriptions: &Subscriptions) -> Option<Message> {
+    if subscriptions.update_format(event.value().event.topic()) == UpdateFormat::Diff
+        && let Some(compact) = event.compact(EventEnvelope::diff_only)
+    {
+        return compact.message(encoding);
+    }
+    event.message(encoding)
+}
+
+fn snapshot_error(topic: Topic) -> ServerMessage {
+    ServerMessage::Error(CommandError {
+        topic: Some(topic),
+        ..CommandError::new("Snapshot gagal, event live tetap dikirim")
+    })
+}
+
+// Memproses satu pesan dari client dan mengembalikan balasannya
+// (`SUBSCRIBED`, `UNSUBSCRIBED`, `REAUTHENTICATED` atau `ERROR`), beserta permintaan snapshot bila ada.
+fn handle_command(
+    msg: &Message,
+    encoding: Encoding,
+    subscriptions: &mut Subscriptions,
+    pending: &mut Option<PendingSnapshot>,
+    session: &mut TokenSession,
+) -> (ServerMessage, Option<SnapshotRequest>) {
+    let result = encoding
+        .decode::<ClientCommand>(msg)
+        .map_err(|e| CommandError::new(format!("Pesan tidak valid: {}", e)))
+        .and_then(|command| match command {
+            ClientCommand::Subscribe { topic, filter, snapshot, page_size, updates } => {
+                if snapshot {
+                    if topic == Topic::Presence {
+                        return Err(CommandError::new("Topik presence tidak mendukung snapshot"));
+                    }
+                    if pending.is_some() {
+                        return Err(CommandError::new("Snapshot lain masih berjalan"));
+                    }
+                }
+                subscriptions.subscribe(topic, filter.as_deref(), updates)?;
+                let request = snapshot.then(|| SnapshotRequest {
+                    topic,
+                    filter: subscriptions.filter(topic).cloned(),
+                    page_size: page_size
+                        .unwrap_or(snapshot::DEFAULT_PAGE_SIZE)
+                        .clamp(1, snapshot::MAX_PAGE_SIZE),
+                });
+                Ok((ServerMessage::Subscribed(Subscribed { topic, filter, snapshot, updates }), request))
+            }
+            ClientCommand::Unsubscribe { topic } => {
+                subscriptions.unsubscribe(topic);
+                // Snapshot untuk topik yang dilepas tidak lagi berguna; task dibatalkan saat dilepas.
+                if pending.as_ref().is_some_and(|p| p.topic == topic) {
+                    *pending = None;
+                }
+                Ok((ServerMessage::Unsubscribed { topic }, None))
+            }
+            ClientCommand::Reauthenticate { token } => {
+                let expires_at = session.reauthenticate(&token)?;
+                Ok((ServerMessage::Reauthenticated { expires_at }, None))
+            }
+        });
+    result.unwrap_or_else(|e| (ServerMessage::Error(e), None))
+}
\ No newline at end of file
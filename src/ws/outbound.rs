@@ -0,0 +1,310 @@
+use axum::extract::ws::{CloseFrame, Message, WebSocket};
+use futures_util::{stream::SplitSink, SinkExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+// Close code untuk client yang terlalu lambat membaca (rentang 4000-4999 bebas dipakai aplikasi).
+pub const CLOSE_SLOW_CONSUMER: u16 = 4008;
+// Close code untuk koneksi yang diusir karena user membuka koneksi melebihi batas.
+pub const CLOSE_CONNECTION_LIMIT: u16 = 4009;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    // Buang pesan tertua di antrean.
+    DropOldest,
+    // Ganti pesan antrean untuk entitas yang sama dengan versi terbaru; jika tidak ada, buang yang tertua.
+    Coalesce,
+    // Tutup koneksi dengan `CLOSE_SLOW_CONSUMER`.
+    Disconnect,
+}
+
+impl OverflowPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "drop_oldest" => Some(OverflowPolicy::DropOldest),
+            "coalesce" => Some(OverflowPolicy::Coalesce),
+            "disconnect" => Some(OverflowPolicy::Disconnect),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct QueueStats {
+    capacity: AtomicUsize,
+    len: AtomicUsize,
+    high_water: AtomicUsize,
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    coalesced: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueMetrics {
+    pub capacity: usize,
+    pub len: usize,
+    pub high_water: usize,
+    pub sent: u64,
+    pub dropped: u64,
+    pub coalesced: u64,
+}
+
+impl QueueStats {
+    pub fn snapshot(&self) -> QueueMetrics {
+        QueueMetrics {
+            capacity: self.capacity.load(Ordering::Relaxed),
+            len: self.len.load(Ordering::Relaxed),
+            high_water: self.high_water.load(Ordering::Relaxed),
+            sent: self.sent.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            coalesced: self.coalesced.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct Outbound {
+    message: Message,
+    key: Option<String>,
+    // Dimasukkan lewat `push_wait`; tidak pernah dibuang atau ditimpa oleh `push`.
+    protected: bool,
+}
+
+struct Inner {
+    items: VecDeque<Outbound>,
+    closing: Option<Option<CloseFrame>>,
+}
+
+// Antrean keluar berkapasitas tetap untuk satu koneksi. Loop utama tidak pernah
+// menunggu socket; pengiriman dilakukan oleh `write_loop` di task terpisah.
+pub struct OutboundQueue {
+    capacity: usize,
+    policy: OverflowPolicy,
+    inner: Mutex<Inner>,
+    notify: Notify,
+    space: Notify,
+    stats: Arc<QueueStats>,
+}
+
+impl OutboundQueue {
+    pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        let capacity = capacity.max(1);
+        let stats = QueueStats { capacity: AtomicUsize::new(capacity), ..Default::default() };
+        Self {
+            capacity,
+            policy,
+            inner: Mutex::new(Inner { items: VecDeque::with_capacity(capacity), closing: None }),
+            notify: Notify::new(),
+            space: Notify::new(),
+            stats: Arc::new(stats),
+        }
+    }
+
+    pub fn stats(&self) -> Arc<QueueStats> {
+        self.stats.clone()
+    }
+
+    // Mengembalikan false jika koneksi harus ditutup karena antrean penuh. Saat penuh, yang
+    // dibuang adalah pesan tertua yang tidak dilindungi; jika semua pesan dilindungi
+    // (misalnya halaman snapshot), pesan baru inilah yang dibuang.
+    pub fn push(&self, message: Message, key: Option<String>) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.closing.is_some() {
+            return false;
+        }
+        if inner.items.len() >= self.capacity {
+            match self.policy {
+                OverflowPolicy::Disconnect => {
+                    drop(inner);
+                    self.close(Some(CloseFrame {
+                        code: CLOSE_SLOW_CONSUMER,
+                        reason: "slow consumer".into(),
+                    }));
+                    return false;
+                }
+                OverflowPolicy::Coalesce | OverflowPolicy::DropOldest => {
+                    if self.policy == OverflowPolicy::Coalesce {
+                        let existing = key.as_ref().and_then(|key| {
+                            inner.items.iter_mut().find(|item| !item.protected && item.key.as_ref() == Some(key))
+                        });
+                        if let Some(existing) = existing {
+                            existing.message = message;
+                            self.stats.coalesced.fetch_add(1, Ordering::Relaxed);
+                            return true;
+                        }
+                    }
+                    self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                    match inner.items.iter().position(|item| !item.protected) {
+                        Some(oldest) => {
+                            inner.items.remove(oldest);
+                        }
+                        None => return true,
+                    }
+                }
+            }
+        }
+        inner.items.push_back(Outbound { message, key, protected: false });
+        let len = inner.items.len();
+        self.stats.len.store(len, Ordering::Relaxed);
+        self.stats.high_water.fetch_max(len, Ordering::Relaxed);
+        drop(inner);
+        self.notify.notify_one();
+        true
+    }
+
+    // Seperti `push`, tetapi menunggu sampai antrean punya ruang alih-alih membuang pesan,
+    // dan pesannya tidak akan dibuang oleh `push` berikutnya. Dipakai untuk data yang tidak
+    // boleh hilang, misalnya halaman snapshot.
+    pub async fn push_wait(&self, message: Message) -> bool {
+        loop {
+            {
+                let mut inner = self.inner.lock().unwrap();
+                if inner.closing.is_some() {
+                    return false;
+                }
+                if inner.items.len() < self.capacity {
+                    inner.items.push_back(Outbound { message, key: None, protected: true });
+                    let len = inner.items.len();
+                    self.stats.len.store(len, Ordering::Relaxed);
+                    self.stats.high_water.fetch_max(len, Ordering::Relaxed);
+                    drop(inner);
+                    self.notify.notify_one();
+                    return true;
+                }
+            }
+            self.space.notified().await;
+        }
+    }
+
+    // Membuang pesan yang belum terkirim lalu meminta writer mengirim close frame.
+    pub fn close(&self, frame: Option<CloseFrame>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.closing.is_none() {
+            self.stats.dropped.fetch_add(inner.items.len() as u64, Ordering::Relaxed);
+            inner.items.clear();
+            inner.closing = Some(frame);
+            self.stats.len.store(0, Ordering::Relaxed);
+        }
+        drop(inner);
+        self.notify.notify_one();
+        self.space.notify_one();
+    }
+
+    // Mengirim pesan yang masih di antrean terlebih dahulu, baru kemudian close frame.
+    pub fn finish(&self, frame: Option<CloseFrame>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.closing.is_none() {
+            inner.closing = Some(frame);
+        }
+        drop(inner);
+        self.notify.notify_one();
+    }
+
+    async fn next(&self) -> Result<Message, Option<CloseFrame>> {
+        loop {
+            {
+                let mut inner = self.inner.lock().unwrap();
+                if let Some(item) = inner.items.pop_front() {
+                    self.stats.len.store(inner.items.len(), Ordering::Relaxed);
+                    self.space.notify_one();
+                    return Ok(item.message);
+                }
+                if let Some(frame) = &inner.closing {
+                    return Err(frame.clone());
+                }
+            }
+            self.notify.notified().await;
+        }
+    }
+}
+
+// Setiap pengiriman dibatasi `send_timeout`: client yang berhenti membaca socket TCP-nya
+// membuat `send` menunggu selamanya, jadi writer berhenti dan antrean ditutup.
+pub async fn write_loop(mut sink: SplitSink<WebSocket, Message>, queue: Arc<OutboundQueue>, send_timeout: Duration) {
+    loop {
+        match queue.next().await {
+            Ok(message) => match tokio::time::timeout(send_timeout, sink.send(message)).await {
+                Ok(Ok(())) => {
+                    queue.stats.sent.fetch_add(1, Ordering::Relaxed);
+                }
+                // Client terputus
+                Ok(Err(_)) => break,
+                Err(_) => {
+                    tracing::warn!("Pengiriman WebSocket melewati {:?}, writer berhenti", send_timeout);
+                    break;
+                }
+            },
+            Err(frame) => {
+                let _ = tokio::time::timeout(send_timeout, sink.send(Message::Close(frame))).await;
+                return;
+            }
+        }
+    }
+    // Pesan yang tersisa tidak akan pernah terkirim; `push` berikutnya mengembalikan false.
+    queue.close(None);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn text(value: &str) -> Message {
+        Message::Text(value.into())
+    }
+
+    async fn drain(queue: &OutboundQueue) -> Vec<Message> {
+        let mut messages = Vec::new();
+        queue.finish(None);
+        while let Ok(message) = queue.next().await {
+            messages.push(message);
+        }
+        messages
+    }
+
+    #[tokio::test]
+    async fn lossy_push_never_evicts_snapshot_pages() {
+        let queue = OutboundQueue::new(3, OverflowPolicy::DropOldest);
+        assert!(queue.push_wait(text("page-1")).await);
+        assert!(queue.push(text("live-1"), None));
+        assert!(queue.push_wait(text("page-2")).await);
+        // Penuh: yang dibuang `live-1`, bukan halaman snapshot.
+        assert!(queue.push(text("live-2"), None));
+        assert_eq!(drain(&queue).await, vec![text("page-1"), text("page-2"), text("live-2")]);
+        assert_eq!(queue.stats().snapshot().dropped, 1);
+    }
+
+    #[tokio::test]
+    async fn coalesce_skips_protected_messages() {
+        let queue = OutboundQueue::new(2, OverflowPolicy::Coalesce);
+        assert!(queue.push_wait(text("page-1")).await);
+        assert!(queue.push(text("book-1 v1"), Some("book:1".to_string())));
+        assert!(queue.push(text("book-1 v2"), Some("book:1".to_string())));
+        assert!(queue.push(text("book-2"), Some("book:2".to_string())));
+        assert_eq!(drain(&queue).await, vec![text("page-1"), text("book-2")]);
+        let stats = queue.stats().snapshot();
+        assert_eq!((stats.coalesced, stats.dropped), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn full_queue_of_protected_pages_drops_the_new_message() {
+        let queue = OutboundQueue::new(2, OverflowPolicy::DropOldest);
+        assert!(queue.push_wait(text("page-1")).await);
+        assert!(queue.push_wait(text("page-2")).await);
+        assert!(queue.push(text("live"), None));
+        assert_eq!(queue.stats().snapshot().dropped, 1);
+
+        // `push_wait` pada antrean penuh menunggu sampai writer mengambil satu pesan.
+        let waiting = queue.push_wait(text("snapshot-end"));
+        tokio::pin!(waiting);
+        assert!(tokio::time::timeout(Duration::from_millis(50), &mut waiting).await.is_err());
+        assert_eq!(queue.next().await, Ok(text("page-1")));
+        assert!(waiting.await);
+        assert_eq!(drain(&queue).await, vec![text("page-2"), text("snapshot-end")]);
+    }
+}
@@ -0,0 +1,57 @@
+use super::subscription::CommandError;
+use crate::auth::{self, Claims};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use tokio::time::Instant;
+
+// Masa berlaku token yang mengautentikasi satu koneksi. Token hanya diperiksa saat
+// upgrade, jadi koneksi diberi peringatan sebelum `exp` dan ditutup jika client tidak
+// mengirim `reauthenticate` dengan token baru.
+pub struct TokenSession {
+    claims: Claims,
+    warned: bool,
+}
+
+pub enum Expiry {
+    // Waktunya mengirim `TOKEN_EXPIRING`.
+    Warn(DateTime<Utc>),
+    // Token sudah kedaluwarsa; koneksi harus ditutup.
+    Expired,
+}
+
+impl TokenSession {
+    pub fn new(claims: Claims) -> Self {
+        Self { claims, warned: false }
+    }
+
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.claims.exp, 0).unwrap_or_else(Utc::now)
+    }
+
+    // Saat berikutnya koneksi perlu diperiksa: `warning` sebelum `exp`, lalu tepat di `exp`.
+    pub fn deadline(&self, warning: Duration) -> Instant {
+        let remaining = (self.expires_at() - Utc::now()).to_std().unwrap_or_default();
+        let remaining = if self.warned { remaining } else { remaining.saturating_sub(warning) };
+        Instant::now() + remaining
+    }
+
+    pub fn on_deadline(&mut self) -> Expiry {
+        if self.warned || self.expires_at() <= Utc::now() {
+            Expiry::Expired
+        } else {
+            self.warned = true;
+            Expiry::Warn(self.expires_at())
+        }
+    }
+
+    // Token baru harus valid dan milik user yang sama; role ikut diperbarui.
+    pub fn reauthenticate(&mut self, token: &str) -> Result<DateTime<Utc>, CommandError> {
+        let claims = auth::decode_token(token).map_err(|_| CommandError::new("Token tidak valid atau kedaluwarsa"))?;
+        if claims.sub != self.claims.sub {
+            return Err(CommandError::new("Token milik user lain"));
+        }
+        self.claims = claims;
+        self.warned = false;
+        Ok(self.expires_at())
+    }
+}
@@ -0,0 +1,184 @@
+use super::{
+    codec::Encoding,
+    filter::Filter,
+    message::{ServerMessage, SnapshotItems, SnapshotPage},
+    outbound::OutboundQueue,
+    subscription::Topic,
+};
+use crate::models::{author::Author, book::Book, email::Email};
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 100;
+pub const MAX_PAGE_SIZE: i64 = 1000;
+
+pub struct SnapshotRequest {
+    pub topic: Topic,
+    pub filter: Option<Filter>,
+    pub page_size: i64,
+}
+
+// Hasil snapshot yang sudah selesai dikirim. `pg_snapshot` adalah snapshot transaksi
+// Postgres tempat data dibaca, dipakai untuk memilah event live yang sudah tercermin.
+pub struct SnapshotDone {
+    pub topic: Topic,
+    pub pg_snapshot: String,
+    pub total: usize,
+}
+
+// Task snapshot yang dibatalkan ketika dilepas, misalnya saat koneksi ditutup
+// sebelum snapshot selesai dikirim.
+pub struct SnapshotTask(JoinHandle<anyhow::Result<SnapshotDone>>);
+
+impl SnapshotTask {
+    pub fn spawn(pool: PgPool, queue: Arc<OutboundQueue>, encoding: Encoding, request: SnapshotRequest) -> Self {
+        Self(tokio::spawn(run(pool, queue, encoding, request)))
+    }
+
+    pub async fn join(&mut self) -> anyhow::Result<SnapshotDone> {
+        (&mut self.0).await?
+    }
+}
+
+impl Drop for SnapshotTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+// Membaca seluruh data topik dalam satu transaksi REPEATABLE READ lalu mengirimnya
+// per halaman sebagai pesan `SNAPSHOT`. Halaman dikirim dengan `push_wait` agar
+// tidak terbuang oleh kebijakan overflow antrean.
+async fn run(pool: PgPool, queue: Arc<OutboundQueue>, encoding: Encoding, request: SnapshotRequest) -> anyhow::Result<SnapshotDone> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+    // Query pertama menetapkan snapshot transaksi, jadi nilainya sama dengan
+    // snapshot yang dipakai semua halaman berikutnya.
+    let pg_snapshot = sqlx::query_scalar!("SELECT pg_current_snapshot()::text")
+        .fetch_one(&mut *tx)
+        .await?
+        .unwrap_or_default();
+
+    let mut cursor: Option<(DateTime<Utc>, Uuid)> = None;
+    let mut page = 0;
+    let mut total = 0;
+    loop {
+        let (cursor_at, cursor_id) = cursor.unzip();
+        let (fetched, next) = match request.topic {
+            Topic::Books => {
+                let mut books = sqlx::query_as!(
+                    Book,
+                    "SELECT id, title, author, publication_year, isbn, created_at, updated_at, version, created_by, deleted_at FROM books
+                     WHERE deleted_at IS NULL AND ($1::timestamptz IS NULL OR (created_at, id) < ($1, $2::uuid))
+                     ORDER BY created_at DESC, id DESC
+                     LIMIT $3",
+                    cursor_at,
+                    cursor_id,
+                    request.page_size
+                )
+                .fetch_all(&mut *tx)
+                .await?;
+                let fetched = books.len();
+                let next = books.last().map(|book| (book.created_at, book.id));
+                if let Some(filter) = &request.filter {
+                    books.retain(|book| filter.matches(book));
+                }
+                total += books.len();
+                if !books.is_empty() {
+                    page += 1;
+                    send_page(&queue, encoding, request.topic, page, SnapshotItems::Books(books)).await?;
+                }
+                (fetched, next)
+            }
+            Topic::Authors => {
+                let mut authors = sqlx::query_as!(
+                    Author,
+                    "SELECT id, name, bio, created_at, updated_at FROM authors
+                     WHERE ($1::timestamptz IS NULL OR (created_at, id) < ($1, $2::uuid))
+                     ORDER BY created_at DESC, id DESC
+                     LIMIT $3",
+                    cursor_at,
+                    cursor_id,
+                    request.page_size
+                )
+                .fetch_all(&mut *tx)
+                .await?;
+                let fetched = authors.len();
+                let next = authors.last().map(|author| (author.created_at, author.id));
+                if let Some(filter) = &request.filter {
+                    authors.retain(|author| filter.matches(author));
+                }
+                total += authors.len();
+                if !authors.is_empty() {
+                    page += 1;
+                    send_page(&queue, encoding, request.topic, page, SnapshotItems::Authors(authors)).await?;
+                }
+                (fetched, next)
+            }
+            Topic::Emails => {
+                let mut emails = sqlx::query_as!(
+                    Email,
+                    "SELECT id, sender, recipient, subject, body, sent_at, version, deleted_at FROM emails
+                     WHERE deleted_at IS NULL AND ($1::timestamptz IS NULL OR (sent_at, id) < ($1, $2::uuid))
+                     ORDER BY sent_at DESC, id DESC
+                     LIMIT $3",
+                    cursor_at,
+                    cursor_id,
+                    request.page_size
+                )
+                .fetch_all(&mut *tx)
+                .await?;
+                let fetched = emails.len();
+                let next = emails.last().map(|email| (email.sent_at, email.id));
+                if let Some(filter) = &request.filter {
+                    emails.retain(|email| filter.matches(email));
+                }
+                total += emails.len();
+                if !emails.is_empty() {
+                    page += 1;
+                    send_page(&queue, encoding, request.topic, page, SnapshotItems::Emails(emails)).await?;
+                }
+                (fetched, next)
+            }
+            Topic::Presence => anyhow::bail!("Topik presence tidak mendukung snapshot"),
+        };
+        if (fetched as i64) < request.page_size {
+            break;
+        }
+        cursor = next;
+    }
+    tx.commit().await?;
+
+    Ok(SnapshotDone { topic: request.topic, pg_snapshot, total })
+}
+
+async fn send_page(queue: &OutboundQueue, encoding: Encoding, topic: Topic, page: usize, items: SnapshotItems) -> anyhow::Result<()> {
+    let msg = encoding.encode(&ServerMessage::Snapshot(SnapshotPage { topic, page, items }))?;
+    if !queue.push_wait(msg).await {
+        anyhow::bail!("Koneksi ditutup saat mengirim snapshot");
+    }
+    Ok(())
+}
+
+// Event outbox yang ditulis oleh transaksi yang terlihat di snapshot sudah tercermin
+// dalam data snapshot, sehingga tidak perlu dikirim ulang sebagai delta.
+pub async fn reflected_events(pool: &PgPool, pg_snapshot: &str, event_ids: &[Uuid]) -> Result<HashSet<Uuid>, sqlx::Error> {
+    if event_ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+    let ids = sqlx::query_scalar!(
+        "SELECT event_id FROM event_outbox
+         WHERE event_id = ANY($1) AND pg_visible_in_snapshot(xact_id, $2::text::pg_snapshot)",
+        event_ids,
+        pg_snapshot
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(ids.into_iter().collect())
+}
@@ -0,0 +1,155 @@
+use super::{
+    filter::{FieldType, Filter, FilterError, Filterable},
+    WsEvent,
+};
+use crate::models::{author::Author, book::Book, email::Email};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    Books,
+    Authors,
+    Emails,
+    Presence,
+}
+
+impl Topic {
+    fn filter_fields(self) -> Option<&'static [(&'static str, FieldType)]> {
+        match self {
+            Topic::Books => Some(Book::FIELDS),
+            Topic::Authors => Some(Author::FIELDS),
+            Topic::Emails => Some(Email::FIELDS),
+            Topic::Presence => None,
+        }
+    }
+}
+
+// Bentuk event update yang dikirim ke satu langganan.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateFormat {
+    // Record lengkap beserta `changes`.
+    #[default]
+    Full,
+    // Hanya id record dan `changes`, tanpa mengirim ulang field yang tidak berubah.
+    Diff,
+}
+
+// Pesan yang dikirim client lewat WebSocket, misalnya
+// `{"action": "subscribe", "topic": "books", "filter": "author == \"Tolkien\""}`.
+// Dengan `"snapshot": true` server lebih dulu mengirim data saat ini per halaman
+// (`SNAPSHOT`), lalu `SNAPSHOT_END`, baru kemudian event live. `"updates": "diff"`
+// membuat event update hanya membawa field yang berubah.
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClientCommand {
+    Subscribe {
+        topic: Topic,
+        #[serde(default)]
+        filter: Option<String>,
+        #[serde(default)]
+        snapshot: bool,
+        #[serde(default)]
+        page_size: Option<i64>,
+        #[serde(default)]
+        updates: UpdateFormat,
+    },
+    Unsubscribe {
+        topic: Topic,
+    },
+    // Memperpanjang koneksi dengan JWT baru sebelum token lama kedaluwarsa.
+    Reauthenticate {
+        token: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandError {
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+    // Topik yang terkait dengan error, misalnya snapshot yang gagal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic: Option<Topic>,
+}
+
+impl CommandError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into(), position: None, topic: None }
+    }
+}
+
+impl From<FilterError> for CommandError {
+    fn from(e: FilterError) -> Self {
+        Self { message: format!("Filter tidak valid: {}", e.message), position: Some(e.position), topic: None }
+    }
+}
+
+// Langganan satu koneksi. Sebelum client mengirim `subscribe`, semua event dikirim
+// seperti perilaku awal; setelahnya hanya topik yang dilanggan yang diteruskan.
+#[derive(Default)]
+pub struct Subscriptions {
+    topics: Option<HashMap<Topic, TopicSubscription>>,
+}
+
+struct TopicSubscription {
+    filter: Option<Filter>,
+    updates: UpdateFormat,
+}
+
+impl Subscriptions {
+    pub fn subscribe(&mut self, topic: Topic, filter: Option<&str>, updates: UpdateFormat) -> Result<(), CommandError> {
+        let filter = match filter.map(str::trim).filter(|f| !f.is_empty()) {
+            None => None,
+            Some(expr) => {
+                let fields = topic
+                    .filter_fields()
+                    .ok_or_else(|| CommandError::new("Topik ini tidak mendukung filter"))?;
+                Some(Filter::parse(expr, fields)?)
+            }
+        };
+        self.topics
+            .get_or_insert_with(HashMap::new)
+            .insert(topic, TopicSubscription { filter, updates });
+        Ok(())
+    }
+
+    pub fn filter(&self, topic: Topic) -> Option<&Filter> {
+        self.topics.as_ref()?.get(&topic)?.filter.as_ref()
+    }
+
+    pub fn update_format(&self, topic: Topic) -> UpdateFormat {
+        self.topics
+            .as_ref()
+            .and_then(|topics| topics.get(&topic))
+            .map_or(UpdateFormat::Full, |subscription| subscription.updates)
+    }
+
+    pub fn unsubscribe(&mut self, topic: Topic) {
+        self.topics.get_or_insert_with(HashMap::new).remove(&topic);
+    }
+
+    pub fn matches(&self, event: &WsEvent) -> bool {
+        let Some(topics) = &self.topics else {
+            return true;
+        };
+        let Some(subscription) = topics.get(&event.topic()) else {
+            return false;
+        };
+        let Some(filter) = &subscription.filter else {
+            return true;
+        };
+        // Event hapus hanya membawa id, jadi selalu diteruskan agar client bisa
+        // membuang record yang pernah diterimanya.
+        match event {
+            WsEvent::BookCreated(book) | WsEvent::BookUpdated(book) | WsEvent::BookRestored(book) => filter.matches(book),
+            WsEvent::AuthorCreated(author) | WsEvent::AuthorUpdated(author) => filter.matches(author),
+            WsEvent::EmailCreated(email) | WsEvent::EmailUpdated(email) | WsEvent::EmailRestored(email) => filter.matches(email),
+            _ => true,
+        }
+    }
+}